@@ -0,0 +1,19 @@
+use dialoguer_multiselect::{theme::ColorfulTheme, FlatMultiSelect};
+
+fn main() {
+    let items = ["work (active)", "personal", "experiments"];
+
+    let selections = FlatMultiSelect::new()
+        .with_theme(&ColorfulTheme::default())
+        .with_prompt("Select profiles")
+        .items(items)
+        .defaults(vec![true, false, false])
+        .interact()
+        .unwrap();
+
+    println!("\nSelected indices: {:?}", selections);
+
+    for idx in selections {
+        println!("{}", items[idx]);
+    }
+}
@@ -1,4 +1,4 @@
-use dialoguer_multiselect::{theme::ColorfulTheme, GroupMultiSelect, ItemState};
+use dialoguer_multiselect::{map_selection, theme::ColorfulTheme, GroupMultiSelect, ItemState};
 
 fn main() {
     let selections = GroupMultiSelect::new()
@@ -39,9 +39,8 @@ fn main() {
         &["main"],
     ];
 
-    for (g_idx, indices) in selections.iter().enumerate() {
-        if !indices.is_empty() {
-            let names: Vec<_> = indices.iter().map(|&i| items[g_idx][i]).collect();
+    for (g_idx, names) in map_selection(&selections, &items).iter().enumerate() {
+        if !names.is_empty() {
             println!("{}: {:?}", group_names[g_idx], names);
         }
     }
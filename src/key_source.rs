@@ -0,0 +1,287 @@
+//! Provides key sources for interactive prompts
+
+use std::{io, sync::mpsc, thread, time::Duration};
+
+use console::{Key, Term};
+
+/// Trait for reading key presses.
+///
+/// Prompts that accept a key source read all of their input through this
+/// trait, which allows swapping the terminal backend or scripting input.
+/// It is implemented for [`Term`] by forwarding to [`Term::read_key`], which
+/// can't time out; [`TermKeySource`] reads from a [`Term`] with timeouts.
+pub trait KeySource {
+    /// Blocks until the next key press is available and returns it.
+    fn read_key(&mut self) -> io::Result<Key>;
+
+    /// Waits at most `timeout` for the next key press.
+    ///
+    /// Returns `Ok(None)` if no key arrived in time. The default implementation
+    /// can't time out and blocks like [`read_key`](Self::read_key).
+    fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        let _ = timeout;
+        self.read_key().map(Some)
+    }
+}
+
+impl KeySource for Term {
+    fn read_key(&mut self) -> io::Result<Key> {
+        Term::read_key(self)
+    }
+}
+
+impl KeySource for &Term {
+    fn read_key(&mut self) -> io::Result<Key> {
+        Term::read_key(self)
+    }
+}
+
+/// Reads key presses from a [`Term`] and supports timeouts.
+///
+/// [`Term::read_key`] blocks until a key is pressed, so the first timed read
+/// starts a thread that reads keys and hands them over through a channel.
+/// Until then keys are read directly. The thread is only needed while a
+/// prompt waits for background work, such as
+/// [`recompute_states_in_background`](crate::GroupMultiSelect::recompute_states_in_background).
+///
+/// Once started, the thread keeps waiting after the source is dropped and
+/// discards the next key press before it exits. [`Term`] only enables raw mode
+/// while a read is in progress, so the terminal can't be polled for a pending
+/// key press instead.
+#[derive(Debug)]
+pub struct TermKeySource {
+    term: Term,
+    keys: Option<mpsc::Receiver<io::Result<Key>>>,
+}
+
+impl TermKeySource {
+    /// Creates a key source that reads from `term`.
+    pub fn new(term: &Term) -> Self {
+        Self {
+            term: term.clone(),
+            keys: None,
+        }
+    }
+
+    /// Returns the channel of the reader thread, starting it if needed.
+    fn keys(&mut self) -> &mpsc::Receiver<io::Result<Key>> {
+        let term = &self.term;
+        self.keys.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel();
+            let term = term.clone();
+            thread::spawn(move || loop {
+                let key = term.read_key();
+                let failed = key.is_err();
+                if tx.send(key).is_err() || failed {
+                    break;
+                }
+            });
+            rx
+        })
+    }
+}
+
+impl KeySource for TermKeySource {
+    fn read_key(&mut self) -> io::Result<Key> {
+        if self.keys.is_none() {
+            return self.term.read_key();
+        }
+        match self.keys().recv() {
+            Ok(key) => key,
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "Key reader thread stopped",
+            )),
+        }
+    }
+
+    fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        // Without a terminal keys don't block, and a reader thread would spin
+        if !self.term.is_term() {
+            return self.term.read_key().map(Some);
+        }
+        match self.keys().recv_timeout(timeout) {
+            Ok(key) => key.map(Some),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "Key reader thread stopped",
+            )),
+        }
+    }
+}
+
+impl<S: KeySource + ?Sized> KeySource for &mut S {
+    fn read_key(&mut self) -> io::Result<Key> {
+        (**self).read_key()
+    }
+
+    fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        (**self).read_key_timeout(timeout)
+    }
+}
+
+/// Reads key presses from crossterm's event queue.
+///
+/// Use this instead of [`Term`] when the application already consumes input
+/// through crossterm, so the two libraries don't fight over the terminal.
+///
+/// # Raw mode
+///
+/// Like [`Term::read_key`], this source enables raw mode only while waiting
+/// for a key and restores the previous mode afterwards, so rendering through
+/// [`Term`] keeps working. If raw mode is already enabled when a key is
+/// requested, the application owns it and the source leaves it untouched.
+#[cfg(feature = "crossterm")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CrosstermKeySource;
+
+#[cfg(feature = "crossterm")]
+impl CrosstermKeySource {
+    /// Creates a new crossterm key source.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Maps a crossterm key event to the equivalent [`Key`].
+    ///
+    /// Returns `None` for key releases. Control chords produce the same
+    /// control characters that [`Term::read_key`] reports.
+    pub fn map_event(event: &crossterm::event::KeyEvent) -> Option<Key> {
+        use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+
+        if event.kind == KeyEventKind::Release {
+            return None;
+        }
+
+        let key = match event.code {
+            KeyCode::Char('c') | KeyCode::Char('C')
+                if event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                Key::CtrlC
+            }
+            KeyCode::Char(c)
+                if event.modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii_alphabetic() =>
+            {
+                match (c.to_ascii_lowercase() as u8 & 0x1f) as char {
+                    '\x01' => Key::Home,
+                    '\x05' => Key::End,
+                    '\x08' => Key::Backspace,
+                    '\t' => Key::Tab,
+                    '\n' | '\r' => Key::Enter,
+                    c => Key::Char(c),
+                }
+            }
+            KeyCode::Char(c) => Key::Char(c),
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Left => Key::ArrowLeft,
+            KeyCode::Right => Key::ArrowRight,
+            KeyCode::Up => Key::ArrowUp,
+            KeyCode::Down => Key::ArrowDown,
+            KeyCode::Home => Key::Home,
+            KeyCode::End => Key::End,
+            KeyCode::PageUp => Key::PageUp,
+            KeyCode::PageDown => Key::PageDown,
+            KeyCode::Tab if event.modifiers.contains(KeyModifiers::SHIFT) => Key::BackTab,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::BackTab => Key::BackTab,
+            KeyCode::Delete => Key::Del,
+            KeyCode::Insert => Key::Insert,
+            KeyCode::Esc => Key::Escape,
+            _ => Key::Unknown,
+        };
+
+        Some(key)
+    }
+}
+
+#[cfg(feature = "crossterm")]
+impl CrosstermKeySource {
+    fn read_event(&mut self, timeout: Option<Duration>) -> io::Result<Option<Key>> {
+        use crossterm::{event, terminal};
+
+        let owns_raw_mode = !terminal::is_raw_mode_enabled()?;
+        if owns_raw_mode {
+            terminal::enable_raw_mode()?;
+        }
+
+        let key = loop {
+            if let Some(timeout) = timeout {
+                match event::poll(timeout) {
+                    Ok(true) => {}
+                    Ok(false) => break Ok(None),
+                    Err(err) => break Err(err),
+                }
+            }
+            match event::read() {
+                Ok(event::Event::Key(event)) => {
+                    if let Some(key) = Self::map_event(&event) {
+                        break Ok(Some(key));
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => break Err(err),
+            }
+        };
+
+        if owns_raw_mode {
+            terminal::disable_raw_mode()?;
+        }
+
+        key
+    }
+}
+
+#[cfg(feature = "crossterm")]
+impl KeySource for CrosstermKeySource {
+    fn read_key(&mut self) -> io::Result<Key> {
+        loop {
+            if let Some(key) = self.read_event(None)? {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        self.read_event(Some(timeout))
+    }
+}
+
+#[cfg(all(test, feature = "crossterm"))]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+    use super::*;
+
+    #[test]
+    fn test_map_plain_keys() {
+        let event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(CrosstermKeySource::map_event(&event), Some(Key::Char('j')));
+
+        let event = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(CrosstermKeySource::map_event(&event), Some(Key::ArrowDown));
+
+        let event = KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT);
+        assert_eq!(CrosstermKeySource::map_event(&event), Some(Key::BackTab));
+    }
+
+    #[test]
+    fn test_map_control_chords() {
+        let event = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(CrosstermKeySource::map_event(&event), Some(Key::CtrlC));
+
+        let event = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(
+            CrosstermKeySource::map_event(&event),
+            Some(Key::Char('\x1a'))
+        );
+    }
+
+    #[test]
+    fn test_map_ignores_release() {
+        let event =
+            KeyEvent::new_with_kind(KeyCode::Enter, KeyModifiers::NONE, KeyEventKind::Release);
+        assert_eq!(CrosstermKeySource::map_event(&event), None);
+    }
+}
@@ -26,6 +26,9 @@
 //! * `history`: enables input prompts to be able to track history of inputs
 //! * `password`: enables password input prompt
 //! * `completion`: enables ability to implement custom tab-completion for input prompts
+//! * `crossterm`: enables reading keys from crossterm's event queue
+//! * `async`: enables reading keys of `GroupMultiSelect` from an async stream
+//! * `serde`: implements `Serialize` and `Deserialize` for `Selection`
 //!
 //! By default `editor` and `password` are enabled.
 
@@ -41,6 +44,9 @@ pub use edit::Editor;
 pub use error::{Error, Result};
 #[cfg(feature = "history")]
 pub use history::{BasicHistory, History};
+#[cfg(feature = "crossterm")]
+pub use key_source::CrosstermKeySource;
+pub use key_source::{KeySource, TermKeySource};
 use paging::Paging;
 pub use validate::{InputValidator, PasswordValidator};
 
@@ -50,7 +56,13 @@ pub use prompts::fuzzy_select::FuzzySelect;
 pub use prompts::password::Password;
 pub use prompts::{
     confirm::Confirm,
-    group_multi_select::{Group, GroupMultiSelect, ItemState},
+    flat_multi_select::FlatMultiSelect,
+    group_multi_select::{
+        map_selection, ComponentEvent, DisabledNav, DoubleConfirmWhen, Frame, Group,
+        GroupMultiSelect, GroupMultiSelectComponent, GroupSelection, HeaderEnter, HorizontalKeys,
+        ItemSpec, ItemState, KeyBindings, PromptOutcome, ReportOverflow, SelectedItem, Selection,
+        SelectionOutcome, SpacingConfig, ToggleEvent, WarningToggle,
+    },
     input::Input,
     multi_select::MultiSelect,
     select::Select,
@@ -64,6 +76,7 @@ mod edit;
 mod error;
 #[cfg(feature = "history")]
 mod history;
+mod key_source;
 mod paging;
 mod prompts;
 pub mod theme;
@@ -12,7 +12,7 @@ use crate::{
 /// ## Example
 ///
 /// ```rust,no_run
-/// use dialoguer::Confirm;
+/// use dialoguer_multiselect::Confirm;
 ///
 /// fn main() {
 ///     let confirmation = Confirm::new()
@@ -118,7 +118,7 @@ impl Confirm<'_> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::Confirm;
+    /// use dialoguer_multiselect::Confirm;
     ///
     /// fn main() {
     ///     let confirmation = Confirm::new()
@@ -244,7 +244,7 @@ impl<'a> Confirm<'a> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::{theme::ColorfulTheme, Confirm};
+    /// use dialoguer_multiselect::{theme::ColorfulTheme, Confirm};
     ///
     /// fn main() {
     ///     let confirmation = Confirm::with_theme(&ColorfulTheme::default())
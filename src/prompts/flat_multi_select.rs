@@ -0,0 +1,193 @@
+use console::{Key, Term};
+
+use crate::{prompts::group_multi_select::GroupMultiSelect, theme::Theme, Result};
+
+/// Renders a multi select prompt without groups.
+///
+/// This is a [`GroupMultiSelect`] with a single group whose header row is hidden, so it
+/// shares its rendering, navigation and paging but returns plain item indices.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use dialoguer_multiselect::FlatMultiSelect;
+///
+/// fn main() {
+///     let items = vec!["foo", "bar", "baz"];
+///
+///     let selection = FlatMultiSelect::new()
+///         .with_prompt("What do you choose?")
+///         .items(items.clone())
+///         .interact()
+///         .unwrap();
+///
+///     for i in selection {
+///         println!("{}", items[i]);
+///     }
+/// }
+/// ```
+pub struct FlatMultiSelect<'a, T> {
+    items: Vec<T>,
+    defaults: Vec<bool>,
+    prompt: GroupMultiSelect<'a, T>,
+}
+
+impl<T> Default for FlatMultiSelect<'static, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> FlatMultiSelect<'a, T> {
+    /// Creates a flat multi select prompt with the default theme.
+    pub fn new() -> FlatMultiSelect<'static, T> {
+        FlatMultiSelect {
+            items: Vec::new(),
+            defaults: Vec::new(),
+            prompt: GroupMultiSelect::new(),
+        }
+    }
+
+    /// Creates a flat multi select prompt with a specific theme.
+    pub fn with_theme(self, theme: &'a dyn Theme) -> FlatMultiSelect<'a, T> {
+        FlatMultiSelect {
+            items: self.items,
+            defaults: self.defaults,
+            prompt: self.prompt.with_theme(theme),
+        }
+    }
+
+    /// Adds a single item.
+    pub fn item(mut self, item: T) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Adds multiple items.
+    pub fn items(mut self, items: impl IntoIterator<Item = T>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    /// Sets which items are checked initially.
+    ///
+    /// Missing entries are unchecked and extra entries are ignored.
+    pub fn defaults(mut self, defaults: Vec<bool>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Sets the prompt text.
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = self.prompt.with_prompt(prompt);
+        self
+    }
+
+    /// Sets the maximum number of items shown at once.
+    pub fn max_length(mut self, val: usize) -> Self {
+        self.prompt = self.prompt.max_length(val);
+        self
+    }
+
+    /// Sets whether the prompt is cleared after the selection. The default is `true`.
+    pub fn clear(mut self, val: bool) -> Self {
+        self.prompt = self.prompt.clear(val);
+        self
+    }
+
+    /// Sets whether the selected items are reported after the prompt. The default is
+    /// `true`.
+    pub fn report(mut self, val: bool) -> Self {
+        self.prompt = self.prompt.report(val);
+        self
+    }
+
+    /// Builds the underlying prompt with a single headerless group.
+    fn into_grouped(self) -> GroupMultiSelect<'a, T> {
+        self.prompt
+            .group("", self.items)
+            .defaults(vec![self.defaults])
+            .hide_headers()
+    }
+}
+
+impl<T: ToString> FlatMultiSelect<'_, T> {
+    /// Enables user interaction and returns the indices of the selected items.
+    ///
+    /// The user can select the items with the 'Space' bar and on 'Enter' the indices of
+    /// the selected items will be returned.
+    pub fn interact(self) -> Result<Vec<usize>> {
+        self.interact_on(&Term::stderr())
+    }
+
+    /// Like [`interact`](Self::interact) but allows a specific terminal to be set.
+    pub fn interact_on(self, term: &Term) -> Result<Vec<usize>> {
+        Ok(Self::flatten(self.into_grouped().interact_on(term)?))
+    }
+
+    /// Like [`interact`](Self::interact) but returns `None` if the user quits with 'Esc'
+    /// or 'q'.
+    pub fn interact_opt(self) -> Result<Option<Vec<usize>>> {
+        self.interact_on_opt(&Term::stderr())
+    }
+
+    /// Like [`interact_opt`](Self::interact_opt) but allows a specific terminal to be set.
+    pub fn interact_on_opt(self, term: &Term) -> Result<Option<Vec<usize>>> {
+        Ok(self
+            .into_grouped()
+            .interact_on_opt(term)?
+            .map(Self::flatten))
+    }
+
+    /// Runs the prompt against a list of key events without a terminal.
+    ///
+    /// See [`GroupMultiSelect::run_with_events`].
+    pub fn run_with_events(self, events: Vec<Key>) -> Result<Option<Vec<usize>>> {
+        Ok(self
+            .into_grouped()
+            .run_with_events(events)?
+            .map(Self::flatten))
+    }
+
+    fn flatten(selection: Vec<Vec<usize>>) -> Vec<usize> {
+        selection.into_iter().next().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_header_row() {
+        let component = FlatMultiSelect::new()
+            .with_prompt("Pick")
+            .items(["a", "b", "c"])
+            .defaults(vec![false, true])
+            .into_grouped()
+            .component((10, 80))
+            .unwrap();
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], ">   ☐ a");
+        assert_eq!(lines[2], "    ☑ b");
+    }
+
+    #[test]
+    fn test_run_with_events() {
+        let selection = FlatMultiSelect::new()
+            .items(["a", "b", "c"])
+            .defaults(vec![false, true])
+            .run_with_events(vec![
+                Key::Char(' '),
+                Key::ArrowDown,
+                Key::ArrowDown,
+                Key::Char(' '),
+                Key::ArrowLeft,
+                Key::ArrowUp,
+                Key::Enter,
+            ])
+            .unwrap();
+        assert_eq!(selection, Some(vec![0, 1, 2]));
+    }
+}
@@ -1,10 +1,19 @@
-use std::io;
+use std::{
+    collections::HashMap,
+    io,
+    ops::Range,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
 
-use console::{Key, Term};
+use console::{measure_text_width, Color, Key, Term};
+#[cfg(feature = "async")]
+use futures_core::Stream;
 
 use crate::{
-    theme::{render::TermThemeRenderer, GroupState, SimpleTheme, Theme},
-    Result,
+    theme::{render::TermThemeRenderer, GroupState, RowContext, SimpleTheme, Theme},
+    KeySource, Result, TermKeySource,
 };
 
 /// Represents the state of an item in GroupMultiSelect.
@@ -23,12 +32,24 @@ pub enum ItemState {
         /// Warning message to display
         message: String,
     },
+    /// Locked item - can be focused, always selected and can't be unchecked
+    Locked,
 }
 
+#[derive(Clone)]
 pub struct Group<T> {
     pub label: String,
     pub items: Vec<T>,
     pub states: Vec<ItemState>,
+    /// Whether the group starts collapsed, `None` follows
+    /// [`GroupMultiSelect::collapsed_by_default`]
+    pub collapsed: Option<bool>,
+    /// Description shown in a line below the header
+    pub subtitle: Option<String>,
+    /// Color of the header and the highlighted item, `None` uses the theme colors
+    pub accent: Option<Color>,
+    /// Why the whole group is unavailable, `None` for available groups
+    pub disabled: Option<String>,
 }
 
 impl<T> Group<T> {
@@ -38,31 +59,859 @@ impl<T> Group<T> {
             label: label.into(),
             items,
             states: vec![ItemState::Normal; len],
+            collapsed: None,
+            subtitle: None,
+            accent: None,
+            disabled: None,
         }
     }
 
-    pub fn with_states(label: impl Into<String>, items: Vec<T>, states: Vec<ItemState>) -> Self {
-        Self {
+    /// Creates a group with a state per item.
+    ///
+    /// Returns an error if `states` doesn't have exactly one entry per item.
+    pub fn with_states(
+        label: impl Into<String>,
+        items: Vec<T>,
+        states: Vec<ItemState>,
+    ) -> Result<Self> {
+        if states.len() != items.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Group has {} items but {} states",
+                    items.len(),
+                    states.len()
+                ),
+            )
+            .into());
+        }
+
+        Ok(Self {
             label: label.into(),
             items,
             states,
+            collapsed: None,
+            subtitle: None,
+            accent: None,
+            disabled: None,
+        })
+    }
+
+    /// Sets whether the group starts collapsed.
+    pub fn collapsed(mut self, val: bool) -> Self {
+        self.collapsed = Some(val);
+        self
+    }
+
+    /// Sets a description shown in a line below the header.
+    pub fn subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Sets the color of the header and the highlighted item.
+    pub fn accent(mut self, color: Color) -> Self {
+        self.accent = Some(color);
+        self
+    }
+
+    /// Makes the whole group unavailable for the given reason.
+    ///
+    /// The cursor skips the header and the items, which can't be toggled.
+    pub fn disabled(mut self, reason: impl Into<String>) -> Self {
+        self.disabled = Some(reason.into());
+        self
+    }
+}
+
+/// Complete description of an item for [`GroupMultiSelect::group_specs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSpec<T> {
+    pub value: T,
+    pub state: ItemState,
+    /// Whether the item starts checked
+    pub checked: bool,
+    /// Tooltip shown by [`GroupMultiSelect::focus_tooltip`]
+    pub description: Option<String>,
+}
+
+impl<T> ItemSpec<T> {
+    /// Creates a normal, unchecked item without a description.
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            state: ItemState::Normal,
+            checked: false,
+            description: None,
+        }
+    }
+
+    pub fn state(mut self, state: ItemState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn checked(mut self, val: bool) -> Self {
+        self.checked = val;
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// Detailed result of a [`GroupMultiSelect`] interaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SelectionOutcome {
+    /// Indices of the selected items, per group
+    pub selection: Vec<Vec<usize>>,
+    /// Whether the final selection differs from the defaults
+    pub was_modified: bool,
+    /// Original indices of the items in their final order, per group
+    ///
+    /// Only differs from the original order with [`GroupMultiSelect::reorderable`].
+    pub order: Vec<Vec<usize>>,
+    /// State index of every item in original order, per group
+    ///
+    /// Items of groups with [`GroupMultiSelect::cycle_labels`] report the index of their
+    /// label, all other items `1` if selected and `0` otherwise.
+    pub levels: Vec<Vec<usize>>,
+    /// Group-level flags, per group
+    ///
+    /// Only toggled with [`GroupMultiSelect::group_selection`] enabled, otherwise these
+    /// are the [`group_defaults`](GroupMultiSelect::group_defaults).
+    pub groups: Vec<bool>,
+    /// Whether the prompt was quit instead of accepted
+    ///
+    /// Only set with [`GroupMultiSelect::quit_returns_progress`].
+    pub cancelled: bool,
+}
+
+impl SelectionOutcome {
+    /// Returns the checked state of every item, which can be restored with
+    /// [`GroupMultiSelect::with_selection`].
+    pub fn to_selection(&self) -> Selection {
+        Selection {
+            checked: self
+                .selection
+                .iter()
+                .zip(self.order.iter())
+                .map(|(indices, order)| {
+                    let mut checked = vec![false; order.len()];
+                    for &idx in indices {
+                        if let Some(is_checked) = checked.get_mut(idx) {
+                            *is_checked = true;
+                        }
+                    }
+                    checked
+                })
+                .collect(),
         }
     }
 }
 
+/// A change of a checkbox, reported by [`GroupMultiSelect::on_toggle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ToggleEvent {
+    /// Index of the group
+    pub group_idx: usize,
+    /// Original index of the item, `None` for the group-level flag of
+    /// [`GroupMultiSelect::group_selection`]
+    pub item_idx: Option<usize>,
+    /// Whether the checkbox is checked after the change
+    pub now_checked: bool,
+}
+
+/// Checked state of every item of a [`GroupMultiSelect`], in original order per group.
+///
+/// Obtained from [`SelectionOutcome::to_selection`] or
+/// [`interact_selection`](GroupMultiSelect::interact_selection) and restored with
+/// [`with_selection`](GroupMultiSelect::with_selection). With the `serde` feature it can
+/// be serialized to persist the selection between runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Selection {
+    /// Whether each item is checked, per group
+    pub checked: Vec<Vec<bool>>,
+}
+
+impl Selection {
+    /// Creates a selection from the checked state of every item.
+    pub fn new(checked: Vec<Vec<bool>>) -> Self {
+        Self { checked }
+    }
+}
+
+/// Selected items of a group, returned by [`GroupMultiSelect::interact_detailed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GroupSelection<T> {
+    /// Label of the group
+    pub label: String,
+    /// Selected items in their final order
+    pub selected: Vec<SelectedItem<T>>,
+}
+
+/// An item selected in a [`GroupMultiSelect`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SelectedItem<T> {
+    /// Original index of the item in its group
+    pub index: usize,
+    /// The item itself
+    pub value: T,
+    /// Whether the item was in the [`ItemState::Warning`] state when it was accepted
+    pub had_warning: bool,
+}
+
+type SelectableCallback<'a> = Arc<dyn Fn(&ItemState) -> bool + 'a>;
+type SelectedCallback<'a> = Arc<dyn Fn(&ItemState, bool) -> bool + 'a>;
+type ValidateChangeCallback<'a> =
+    Arc<dyn Fn(&[Vec<bool>]) -> std::result::Result<Option<String>, String> + 'a>;
+type ValidateCallback<'a> = Arc<dyn Fn(&[Vec<bool>]) -> std::result::Result<(), String> + 'a>;
+type VisibleCallback<'a, T> = Arc<dyn Fn(usize, usize, &T, &ItemState, &[Vec<bool>]) -> bool + 'a>;
+type ToggleCallback<'a> = Box<dyn FnMut(&ToggleEvent) + 'a>;
+type FormatItemCallback<'a, T> = Arc<dyn Fn(&T, bool, bool) -> String + 'a>;
+type PrologueCallback<'a> = Arc<dyn Fn() -> Vec<String> + 'a>;
+type StartCallback<'a, T> = Box<dyn FnOnce(&mut Vec<Vec<bool>>, &mut Vec<Group<T>>) + 'a>;
+type RecomputeStatesCallback =
+    Arc<dyn Fn(&[Vec<bool>]) -> Vec<Vec<ItemState>> + Send + Sync + 'static>;
+
+/// Size of the virtual terminal used by [`GroupMultiSelect::run_with_events`].
+const HEADLESS_TERM_SIZE: (u16, u16) = (24, 80);
+
+/// How often the prompt checks for a finished background recompute.
+const RECOMPUTE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a toggled item stays highlighted with [`GroupMultiSelect::toggle_flash`].
+const FLASH_DURATION: Duration = Duration::from_millis(400);
+
+/// Time within which 'Enter' has to be pressed again with [`GroupMultiSelect::double_confirm`].
+const DOUBLE_CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+
+/// Columns of the prompt line kept free of the search query for decorations and paging.
+const QUERY_RESERVED_WIDTH: usize = 24;
+
+/// Number of selection changes that can be undone.
+const UNDO_LIMIT: usize = 100;
+
+/// Control characters sent for Ctrl+Z and Ctrl+Y.
+const UNDO_KEY: Key = Key::Char('\x1a');
+const REDO_KEY: Key = Key::Char('\x19');
+
+/// Parameters of the 64-bit FNV-1a hash behind [`GroupMultiSelect::selection_hash`].
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Columns in front of an item label: cursor, indent and checkbox.
+const ITEM_PREFIX_WIDTH: usize = 6;
+
+/// Hint characters of [`GroupMultiSelect::quick_keys`] in the order they are handed out.
+///
+/// Characters bound to an action by [`GroupMultiSelect::bound_keys`] are skipped.
+const QUICK_KEYS: &str = "123456789bcefimnoprstuvwxz";
+
 #[derive(Clone, Copy, Default)]
 struct Cursor {
     group_idx: usize,
     item_idx: Option<usize>,
 }
 
+/// Which rows are shown and how tall they are, derived while the prompt runs.
+#[derive(Default)]
+struct View {
+    /// Items hidden by `visible_when`, refreshed after every key press
+    hidden: Vec<Vec<bool>>,
+    /// Items that don't match the search query
+    filtered: Vec<Vec<bool>>,
+    /// Groups without any item or label matching the search query
+    filtered_groups: Vec<bool>,
+    /// Lines of the prologues, evaluated when the prompt starts
+    prologue_lines: Vec<Vec<String>>,
+}
+
+/// Rows of the list in display order, computed once per frame or key press.
+struct Layout {
+    rows: Vec<Cursor>,
+    /// Line each row starts at, followed by the total number of lines
+    line_offsets: Vec<usize>,
+}
+
+impl Layout {
+    fn total_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The given rows, leaving out the ones past the end of the list.
+    fn rows(&self, rows: Range<usize>) -> &[Cursor] {
+        let end = rows.end.min(self.rows.len());
+        &self.rows[rows.start.min(end)..end]
+    }
+
+    /// Number of lines the given rows take, leaving out the ones past the end of the list.
+    fn lines(&self, rows: Range<usize>) -> usize {
+        let end = rows.end.min(self.rows.len());
+        self.line_offsets[end] - self.line_offsets[rows.start.min(end)]
+    }
+}
+
+/// Mutable state of a single interaction.
+struct State {
+    checked: Vec<Vec<bool>>,
+    initial: Vec<Vec<bool>>,
+    group_checked: Vec<bool>,
+    initial_group_checked: Vec<bool>,
+    view: View,
+    /// Original index of the item at each position, per group
+    order: Vec<Vec<usize>>,
+    /// State index of each item, `checked` is `true` for every index but `0`
+    levels: Vec<Vec<usize>>,
+    cursor: Cursor,
+    page_offset: usize,
+    capacity: usize,
+    width: usize,
+    /// Id of the background recompute whose result is still pending
+    recompute: Option<u64>,
+    recompute_worker: Option<RecomputeWorker>,
+    pending_keys: Vec<Key>,
+    pending_since: Option<Instant>,
+    status: Option<String>,
+    /// Why the last key press was refused, shown below the list
+    error: Option<String>,
+    review: Option<Review>,
+    /// Warning item waiting for its check to be confirmed
+    pending_warning: Option<Cursor>,
+    /// Key that accepts once the warnings of the selection are confirmed
+    pending_accept: Option<Key>,
+    /// Whether the warning of each item was confirmed before, per group
+    acknowledged: Vec<Vec<bool>>,
+    /// Checked matrices and label indices before each change, oldest first
+    undo: Vec<Snapshot>,
+    /// Changes reverted by undo, most recently undone last
+    redo: Vec<Snapshot>,
+    /// Search query, `Some` while search mode is active
+    query: Option<String>,
+    /// When the first 'Enter' of a double confirmation was pressed
+    confirm_since: Option<Instant>,
+    /// Item toggled last and when, while it is highlighted
+    flash: Option<(Cursor, Instant)>,
+    /// Whether the quit keys end the prompt, the help line leaves them out otherwise
+    allow_quit: bool,
+}
+
+/// Thread that runs the background recomputes of a single interaction.
+///
+/// Jobs queued while a recompute runs are skipped except for the newest one.
+struct RecomputeWorker {
+    jobs: mpsc::Sender<(u64, Vec<Vec<bool>>)>,
+    results: mpsc::Receiver<(u64, Vec<Vec<ItemState>>)>,
+    /// Id of the last job sent to the thread
+    last_job: u64,
+}
+
+impl RecomputeWorker {
+    fn spawn(f: RecomputeStatesCallback) -> Self {
+        let (jobs, job_rx) = mpsc::channel::<(u64, Vec<Vec<bool>>)>();
+        let (result_tx, results) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut job) = job_rx.recv() {
+                while let Ok(newer) = job_rx.try_recv() {
+                    job = newer;
+                }
+                if result_tx.send((job.0, f(&job.1))).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            jobs,
+            results,
+            last_job: 0,
+        }
+    }
+
+    /// Queues a recompute of `checked` and returns its id.
+    fn send(&mut self, checked: Vec<Vec<bool>>) -> u64 {
+        self.last_job += 1;
+        // A closed channel shows up as a disconnect when polling for the result
+        let _ = self.jobs.send((self.last_job, checked));
+        self.last_job
+    }
+}
+
+/// Review screen listing the selected items before the selection is accepted.
+struct Review {
+    /// Items that were selected when the review started
+    items: Vec<Cursor>,
+    cursor: usize,
+}
+
+/// Checked matrix and label indices recorded for undo.
+type Snapshot = (Vec<Vec<bool>>, Vec<Vec<usize>>);
+
+/// Direction of [`GroupMultiSelect::restore`].
+enum Undo {
+    Undo,
+    Redo,
+}
+
+/// Where the cursor starts.
+#[derive(Clone, Copy)]
+enum StartAt {
+    /// Group and item index, `None` for the header
+    Row(usize, Option<usize>),
+    /// The first checked item
+    FirstSelected,
+}
+
+/// What the interaction loop should do after a key press.
+enum Step {
+    Continue,
+    Accept,
+    Quit,
+    Action,
+    /// Accept without finalizing, see [`GroupMultiSelect::continue_key`]
+    Apply,
+}
+
+/// What [`KeyBindings::left`] and [`KeyBindings::right`] do in a [`GroupMultiSelect`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HorizontalKeys {
+    /// 'Left' collapses the group under the cursor and 'Right' expands it
+    #[default]
+    Collapse,
+    /// 'Right' checks the focused item and 'Left' unchecks it
+    ///
+    /// On a header the keys check or uncheck every enabled item of the group. Groups
+    /// can't be collapsed or expanded by key in this mode.
+    Check,
+}
+
+/// What 'Enter' does on a group header in a [`GroupMultiSelect`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeaderEnter {
+    /// 'Enter' accepts the selection
+    #[default]
+    Confirm,
+    /// 'Enter' collapses or expands the group
+    ///
+    /// The selection is accepted with 'Enter' on an item or with the other keys of
+    /// [`KeyBindings::confirm`].
+    ToggleCollapse,
+}
+
+/// How the cursor of a [`GroupMultiSelect`] treats disabled items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DisabledNav {
+    /// The cursor moves past disabled items
+    #[default]
+    Skip,
+    /// The cursor stops on disabled items so their reason can be read, toggling does nothing
+    Stop,
+}
+
+/// When accepting a [`GroupMultiSelect`] needs 'Enter' to be pressed twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DoubleConfirmWhen {
+    /// For every selection
+    Always,
+    /// When nothing is selected
+    WhenEmpty,
+    /// When a selected item has a warning
+    WhenWarnings,
+}
+
+/// How a [`GroupMultiSelect`] report that is wider than the terminal is shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReportOverflow {
+    /// The items continue on indented lines, breaking between items
+    #[default]
+    Wrap,
+    /// Items that don't fit are replaced with a count, e.g. "work, personal, +5 more"
+    Truncate,
+}
+
+/// What checking a [`ItemState::Warning`] item with 'Space' does in a [`GroupMultiSelect`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningToggle {
+    /// The item is checked right away
+    #[default]
+    Silent,
+    /// The warning is shown and [`KeyBindings::accept_warning`] has to be pressed every time
+    /// before the item is checked
+    ConfirmEach,
+    /// Like `ConfirmEach`, but only until the warning of the item was confirmed once
+    BlockUntilAcknowledged,
+}
+
+/// Lines of a single rendered [`GroupMultiSelectComponent`] frame.
+///
+/// Lines are styled by the theme, so they may contain ANSI escape codes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Frame {
+    /// Rendered lines from top to bottom
+    pub lines: Vec<String>,
+}
+
+/// Result of feeding a key to a [`GroupMultiSelectComponent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ComponentEvent {
+    /// The prompt is still running
+    Pending,
+    /// The selection was accepted
+    Accepted {
+        /// Accepted selection
+        selection: SelectionOutcome,
+        /// `false` if it was accepted with the [`continue_key`](GroupMultiSelect::continue_key)
+        finalize: bool,
+    },
+    /// The prompt was cancelled with 'Esc' or 'q'
+    Cancelled,
+    /// The [`action_key`](GroupMultiSelect::action_key) was pressed
+    ActionRequested {
+        /// Selection at the time the key was pressed
+        selection: SelectionOutcome,
+    },
+}
+
+/// How a [`GroupMultiSelect`] interaction ended.
+///
+/// Returned by [`GroupMultiSelect::interact_with_action`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PromptOutcome {
+    /// The selection was accepted
+    ///
+    /// With [`quit_returns_progress`](GroupMultiSelect::quit_returns_progress) quitting
+    /// also ends up here, with [`SelectionOutcome::cancelled`] set and `finalize` unset.
+    Accepted {
+        /// Accepted selection
+        selection: SelectionOutcome,
+        /// `false` if it was accepted with the [`continue_key`](GroupMultiSelect::continue_key)
+        finalize: bool,
+    },
+    /// The prompt was cancelled with 'Esc' or 'q'
+    Cancelled,
+    /// The [`action_key`](GroupMultiSelect::action_key) was pressed
+    ///
+    /// The caller can run the action and re-open the prompt with the selection as
+    /// [`defaults`](GroupMultiSelect::defaults).
+    ActionRequested {
+        /// Selection at the time the key was pressed
+        selection: SelectionOutcome,
+    },
+}
+
+/// A [`GroupMultiSelect`] driven by the caller instead of a terminal.
+///
+/// Created with [`GroupMultiSelect::component`].
+pub struct GroupMultiSelectComponent<'a, T> {
+    prompt: GroupMultiSelect<'a, T>,
+    state: State,
+}
+
+impl<T: ToString> GroupMultiSelectComponent<'_, T> {
+    /// Renders the current state.
+    pub fn frame(&self) -> Result<Frame> {
+        Ok(Frame {
+            lines: self.prompt.frame_lines(&self.state)?,
+        })
+    }
+
+    /// Applies a key press.
+    pub fn handle_key(&mut self, key: Key) -> ComponentEvent {
+        let checked_before = self
+            .prompt
+            .recompute_states
+            .as_ref()
+            .map(|_| self.state.checked.clone());
+        let toggle_before = self.prompt.toggle_snapshot(&self.state);
+
+        match self.prompt.handle_key(&mut self.state, key, true) {
+            Step::Continue => {
+                self.prompt.spawn_recompute(&mut self.state, checked_before);
+                if let Some(before) = toggle_before {
+                    self.prompt.emit_toggles(&self.state, &before);
+                }
+                ComponentEvent::Pending
+            }
+            Step::Accept => ComponentEvent::Accepted {
+                selection: self.outcome(),
+                finalize: true,
+            },
+            Step::Apply => ComponentEvent::Accepted {
+                selection: self.outcome(),
+                finalize: false,
+            },
+            Step::Quit => ComponentEvent::Cancelled,
+            Step::Action => ComponentEvent::ActionRequested {
+                selection: self.outcome(),
+            },
+        }
+    }
+
+    /// Applies item states computed in the background.
+    ///
+    /// Returns `true` if the frame changed. Only needed with
+    /// [`recompute_states_in_background`](GroupMultiSelect::recompute_states_in_background)
+    /// and [`toggle_flash`](GroupMultiSelect::toggle_flash).
+    pub fn poll(&mut self) -> bool {
+        let recomputed = self.prompt.poll_recompute(&mut self.state);
+        GroupMultiSelect::<T>::expire_flash(&mut self.state) || recomputed
+    }
+
+    /// Updates the `(rows, columns)` area available to the prompt.
+    pub fn resize(&mut self, term_size: (u16, u16)) {
+        self.prompt.resize(&mut self.state, term_size);
+    }
+
+    /// Returns the current selection.
+    pub fn outcome(&self) -> SelectionOutcome {
+        self.prompt.build_outcome(&self.state)
+    }
+
+    /// Applies key presses until the prompt finishes and returns its selection.
+    ///
+    /// Returns `None` if the keys run out first. Background recomputes finish before the
+    /// next key is applied.
+    fn run_keys(&mut self, keys: impl IntoIterator<Item = Key>) -> Option<Option<Vec<Vec<usize>>>> {
+        keys.into_iter().find_map(|key| self.run_key(key))
+    }
+
+    /// Applies a single key press of [`run_keys`](Self::run_keys).
+    ///
+    /// Returns `None` while the prompt is still running, after waiting for the background
+    /// recompute the key started.
+    fn run_key(&mut self, key: Key) -> Option<Option<Vec<Vec<usize>>>> {
+        match self.handle_key(key) {
+            ComponentEvent::Pending => {}
+            ComponentEvent::Accepted { selection, .. } => return Some(Some(selection.selection)),
+            ComponentEvent::Cancelled if self.prompt.quit_returns_progress => {
+                return Some(Some(self.outcome().selection))
+            }
+            ComponentEvent::Cancelled | ComponentEvent::ActionRequested { .. } => {
+                return Some(None)
+            }
+        }
+        while self.state.recompute.is_some() {
+            if !self.poll() {
+                thread::sleep(RECOMPUTE_POLL_INTERVAL);
+            }
+        }
+        None
+    }
+}
+
+/// Key bindings for [`GroupMultiSelect`].
+///
+/// Jump bindings are key sequences: every key of a sequence has to be pressed within
+/// `sequence_timeout` of the previous one. Keys that start a sequence do nothing on
+/// their own until the sequence completes, is broken by another key or times out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyBindings {
+    /// Sequences that move the cursor to the first row
+    pub top: Vec<Vec<Key>>,
+    /// Sequences that move the cursor to the last row
+    pub bottom: Vec<Vec<Key>>,
+    /// Maximum delay between two keys of a sequence
+    pub sequence_timeout: Duration,
+    /// Behavior of the horizontal arrow keys
+    pub horizontal: HorizontalKeys,
+    /// Keys that accept the selection
+    pub confirm: Vec<Key>,
+    /// Keys that toggle every selectable item, including the ones on other pages
+    pub select_all: Vec<Key>,
+    /// Keys that toggle the selectable items on the current page only
+    pub select_visible: Vec<Key>,
+    /// Keys that invert the selection of every enabled item
+    pub invert: Vec<Key>,
+    /// Keys that check every enabled item of the group under the cursor
+    pub select_group: Vec<Key>,
+    /// Keys that toggle the item or group under the cursor
+    pub toggle: Vec<Key>,
+    /// Keys that move the cursor one row up
+    pub move_up: Vec<Key>,
+    /// Keys that move the cursor one row down
+    pub move_down: Vec<Key>,
+    /// Keys that quit the prompt where quitting is allowed
+    pub quit: Vec<Key>,
+    /// Keys that act like 'Left', see [`horizontal`](Self::horizontal)
+    pub left: Vec<Key>,
+    /// Keys that act like 'Right', see [`horizontal`](Self::horizontal)
+    pub right: Vec<Key>,
+    /// Keys that hide or show the disabled items
+    pub hide_disabled: Vec<Key>,
+    /// Keys that move the focused item up, see [`GroupMultiSelect::reorderable`]
+    pub reorder_up: Vec<Key>,
+    /// Keys that move the focused item down, see [`GroupMultiSelect::reorderable`]
+    pub reorder_down: Vec<Key>,
+    /// Keys that start a search, see [`GroupMultiSelect::search`]
+    pub search: Vec<Key>,
+    /// Keys that uncheck every item of the group when the cursor is on its header
+    pub clear_group: Vec<Key>,
+    /// Keys that confirm a warning shown in the status line
+    pub accept_warning: Vec<Key>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            top: vec![vec![Key::Char('g'), Key::Char('g')], vec![Key::Home]],
+            bottom: vec![vec![Key::Char('G')], vec![Key::End]],
+            sequence_timeout: Duration::from_secs(1),
+            horizontal: HorizontalKeys::Collapse,
+            confirm: vec![Key::Enter],
+            select_all: vec![Key::Char('a')],
+            select_visible: vec![Key::Char('A')],
+            invert: vec![Key::Char('i')],
+            select_group: vec![Key::Char('S')],
+            toggle: vec![Key::Char(' ')],
+            move_up: vec![Key::ArrowUp, Key::Char('k')],
+            move_down: vec![Key::ArrowDown, Key::Char('j')],
+            quit: vec![Key::Escape, Key::Char('q')],
+            left: vec![Key::ArrowLeft, Key::Char('h')],
+            right: vec![Key::ArrowRight, Key::Char('l')],
+            hide_disabled: vec![Key::Char('d')],
+            reorder_up: vec![Key::Char('K')],
+            reorder_down: vec![Key::Char('J')],
+            search: vec![Key::Char('/')],
+            clear_group: vec![Key::Backspace],
+            accept_warning: vec![Key::Char('y')],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// The default bindings with 'x' toggling as well.
+    pub fn vim() -> Self {
+        Self {
+            toggle: vec![Key::Char(' '), Key::Char('x')],
+            ..Self::default()
+        }
+    }
+
+    /// The default bindings without the letter keys for moving and quitting.
+    ///
+    /// The cursor only moves with the arrow keys, 'Home' and 'End', and only 'Esc' quits.
+    pub fn plain() -> Self {
+        Self {
+            top: vec![vec![Key::Home]],
+            bottom: vec![vec![Key::End]],
+            move_up: vec![Key::ArrowUp],
+            move_down: vec![Key::ArrowDown],
+            quit: vec![Key::Escape],
+            left: vec![Key::ArrowLeft],
+            right: vec![Key::ArrowRight],
+            ..Self::default()
+        }
+    }
+}
+
+/// Blank lines around the list of a [`GroupMultiSelect`].
+///
+/// Blank lines take rows away from the list when paging.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SpacingConfig {
+    /// Blank line between the prompt and the list
+    pub after_prompt: bool,
+    /// Blank line below the list
+    pub after_list: bool,
+}
+
+impl SpacingConfig {
+    /// Number of blank lines added.
+    fn lines(&self) -> usize {
+        self.after_prompt as usize + self.after_list as usize
+    }
+}
+
 pub struct GroupMultiSelect<'a, T> {
     groups: Vec<Group<T>>,
     defaults: Vec<Vec<bool>>,
+    group_selection: bool,
+    group_defaults: Vec<bool>,
+    tooltips: Vec<Vec<Option<String>>>,
+    focus_tooltip: bool,
+    show_help: bool,
+    help_text: Option<String>,
     prompt: String,
     report: bool,
+    report_counts: bool,
+    report_overflow: ReportOverflow,
+    report_on_quit: Option<String>,
+    quit_returns_progress: bool,
+    machine_output: Option<Box<dyn io::Write + 'a>>,
+    live_output: Option<Box<dyn io::Write + 'a>>,
+    on_toggle: Option<ToggleCallback<'a>>,
     clear: bool,
+    debug_no_clear: bool,
+    hide_headers: bool,
     max_length: Option<usize>,
+    max_height_fraction: Option<f32>,
+    paging: bool,
+    groups_per_page: Option<usize>,
+    scroll_off: usize,
+    show_scroll_indicators: bool,
+    wrap_around: bool,
+    item_tab: bool,
+    auto_deselect_on_disable: bool,
+    mark_changes: bool,
+    toggle_flash: bool,
+    quick_keys: bool,
+    align_labels: bool,
+    show_count: bool,
+    show_group_counts: bool,
+    review_step: bool,
+    focus_on_violation: bool,
+    min_selections: Option<usize>,
+    max_selections: Option<usize>,
+    group_min: HashMap<usize, usize>,
+    group_max: HashMap<usize, usize>,
+    double_confirm: Option<DoubleConfirmWhen>,
+    confirm_warnings: bool,
+    warning_toggle: WarningToggle,
+    reorderable: bool,
+    select_all_selectable: Option<SelectableCallback<'a>>,
+    select_all_selected: Option<SelectedCallback<'a>>,
+    validate_change: Option<ValidateChangeCallback<'a>>,
+    validate_with: Option<ValidateCallback<'a>>,
+    visible_when: Option<VisibleCallback<'a, T>>,
+    format_item: Option<FormatItemCallback<'a, T>>,
+    prologues: HashMap<usize, PrologueCallback<'a>>,
+    recompute_states: Option<RecomputeStatesCallback>,
+    on_start: Option<StartCallback<'a, T>>,
+    keybindings: KeyBindings,
+    spacing: SpacingConfig,
+    search: bool,
+    filter_matches_groups: bool,
+    header_enter: HeaderEnter,
+    action_key: Option<Key>,
+    continue_key: Option<Key>,
+    boxed: bool,
+    highlight_active_group: bool,
+    collapsed_by_default: bool,
+    auto_expand_on_focus: bool,
+    hide_disabled: bool,
+    disabled_navigation: DisabledNav,
+    reveal: Option<StartAt>,
+    initial_page_offset: Option<usize>,
+    cycle_labels: Vec<Vec<String>>,
+    exclusive_groups: Vec<Vec<usize>>,
     theme: &'a dyn Theme,
 }
 
@@ -77,22 +926,179 @@ impl<'a, T> GroupMultiSelect<'a, T> {
         GroupMultiSelect {
             groups: Vec::new(),
             defaults: Vec::new(),
+            group_selection: false,
+            group_defaults: Vec::new(),
+            tooltips: Vec::new(),
+            focus_tooltip: false,
+            show_help: false,
+            help_text: None,
             prompt: String::new(),
             report: true,
+            report_counts: false,
+            report_overflow: ReportOverflow::Wrap,
+            report_on_quit: None,
+            quit_returns_progress: false,
+            machine_output: None,
+            live_output: None,
+            on_toggle: None,
             clear: true,
+            debug_no_clear: false,
+            hide_headers: false,
             max_length: None,
+            max_height_fraction: None,
+            paging: true,
+            groups_per_page: None,
+            scroll_off: 0,
+            show_scroll_indicators: true,
+            wrap_around: false,
+            item_tab: false,
+            auto_deselect_on_disable: true,
+            mark_changes: false,
+            toggle_flash: false,
+            quick_keys: false,
+            align_labels: false,
+            show_count: false,
+            show_group_counts: true,
+            review_step: false,
+            focus_on_violation: false,
+            min_selections: None,
+            max_selections: None,
+            group_min: HashMap::new(),
+            group_max: HashMap::new(),
+            double_confirm: None,
+            confirm_warnings: false,
+            warning_toggle: WarningToggle::Silent,
+            reorderable: false,
+            select_all_selectable: None,
+            select_all_selected: None,
+            validate_change: None,
+            validate_with: None,
+            visible_when: None,
+            format_item: None,
+            prologues: HashMap::new(),
+            recompute_states: None,
+            on_start: None,
+            keybindings: KeyBindings::default(),
+            spacing: SpacingConfig::default(),
+            search: false,
+            filter_matches_groups: false,
+            header_enter: HeaderEnter::Confirm,
+            action_key: None,
+            continue_key: None,
+            boxed: false,
+            highlight_active_group: false,
+            collapsed_by_default: false,
+            auto_expand_on_focus: false,
+            hide_disabled: false,
+            disabled_navigation: DisabledNav::Skip,
+            reveal: None,
+            initial_page_offset: None,
+            cycle_labels: Vec::new(),
+            exclusive_groups: Vec::new(),
             theme: &SimpleTheme,
         }
     }
 
+    /// Creates a prompt from a flat list, grouping the items by the label `key` returns.
+    ///
+    /// Groups are ordered by their first item and items keep their relative order, so
+    /// the returned indices refer to the items within each derived group.
+    pub fn from_grouped_by<F>(items: Vec<T>, key: F) -> GroupMultiSelect<'static, T>
+    where
+        F: Fn(&T) -> String,
+    {
+        let mut prompt = Self::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for item in items {
+            let label = key(&item);
+            match positions.get(&label) {
+                Some(&group_idx) => {
+                    let group = &mut prompt.groups[group_idx];
+                    group.items.push(item);
+                    group.states.push(ItemState::Normal);
+                }
+                None => {
+                    positions.insert(label.clone(), prompt.groups.len());
+                    prompt.groups.push(Group::new(label, vec![item]));
+                }
+            }
+        }
+        prompt
+    }
+
     pub fn with_theme(self, theme: &'a dyn Theme) -> GroupMultiSelect<'a, T> {
         GroupMultiSelect {
             groups: self.groups,
             defaults: self.defaults,
+            group_selection: self.group_selection,
+            group_defaults: self.group_defaults,
+            tooltips: self.tooltips,
+            focus_tooltip: self.focus_tooltip,
+            show_help: self.show_help,
+            help_text: self.help_text,
             prompt: self.prompt,
             report: self.report,
+            report_counts: self.report_counts,
+            report_overflow: self.report_overflow,
+            report_on_quit: self.report_on_quit,
+            quit_returns_progress: self.quit_returns_progress,
+            machine_output: self.machine_output,
+            live_output: self.live_output,
+            on_toggle: self.on_toggle,
             clear: self.clear,
+            debug_no_clear: self.debug_no_clear,
+            hide_headers: self.hide_headers,
             max_length: self.max_length,
+            max_height_fraction: self.max_height_fraction,
+            paging: self.paging,
+            groups_per_page: self.groups_per_page,
+            scroll_off: self.scroll_off,
+            show_scroll_indicators: self.show_scroll_indicators,
+            wrap_around: self.wrap_around,
+            item_tab: self.item_tab,
+            auto_deselect_on_disable: self.auto_deselect_on_disable,
+            mark_changes: self.mark_changes,
+            toggle_flash: self.toggle_flash,
+            quick_keys: self.quick_keys,
+            align_labels: self.align_labels,
+            show_count: self.show_count,
+            show_group_counts: self.show_group_counts,
+            review_step: self.review_step,
+            focus_on_violation: self.focus_on_violation,
+            min_selections: self.min_selections,
+            max_selections: self.max_selections,
+            group_min: self.group_min,
+            group_max: self.group_max,
+            double_confirm: self.double_confirm,
+            confirm_warnings: self.confirm_warnings,
+            warning_toggle: self.warning_toggle,
+            reorderable: self.reorderable,
+            select_all_selectable: self.select_all_selectable,
+            select_all_selected: self.select_all_selected,
+            validate_change: self.validate_change,
+            validate_with: self.validate_with,
+            visible_when: self.visible_when,
+            format_item: self.format_item,
+            prologues: self.prologues,
+            recompute_states: self.recompute_states,
+            on_start: self.on_start,
+            keybindings: self.keybindings,
+            spacing: self.spacing,
+            search: self.search,
+            filter_matches_groups: self.filter_matches_groups,
+            header_enter: self.header_enter,
+            action_key: self.action_key,
+            continue_key: self.continue_key,
+            boxed: self.boxed,
+            highlight_active_group: self.highlight_active_group,
+            collapsed_by_default: self.collapsed_by_default,
+            auto_expand_on_focus: self.auto_expand_on_focus,
+            hide_disabled: self.hide_disabled,
+            disabled_navigation: self.disabled_navigation,
+            reveal: self.reveal,
+            initial_page_offset: self.initial_page_offset,
+            cycle_labels: self.cycle_labels,
+            exclusive_groups: self.exclusive_groups,
             theme,
         }
     }
@@ -108,532 +1114,7291 @@ impl<'a, T> GroupMultiSelect<'a, T> {
         items: Vec<(T, ItemState)>,
     ) -> Self {
         let (items, states): (Vec<T>, Vec<ItemState>) = items.into_iter().unzip();
-        self.groups.push(Group::with_states(label, items, states));
+        self.groups.push(Group {
+            label: label.into(),
+            items,
+            states,
+            collapsed: None,
+            subtitle: None,
+            accent: None,
+            disabled: None,
+        });
         self
     }
 
-    pub fn defaults(mut self, defaults: Vec<Vec<bool>>) -> Self {
-        self.defaults = defaults;
+    /// Adds a group whose items each carry their state, default and description.
+    ///
+    /// Fills in the [`defaults`](Self::defaults) and [`tooltips`](Self::tooltips) of the
+    /// group, so calling those afterwards replaces what the specs set.
+    pub fn group_specs(mut self, label: impl Into<String>, specs: Vec<ItemSpec<T>>) -> Self {
+        let group_idx = self.groups.len();
+        let mut items = Vec::with_capacity(specs.len());
+        let mut states = Vec::with_capacity(specs.len());
+        let mut defaults = Vec::with_capacity(specs.len());
+        let mut tooltips = Vec::with_capacity(specs.len());
+        for spec in specs {
+            items.push(spec.value);
+            states.push(spec.state);
+            defaults.push(spec.checked);
+            tooltips.push(spec.description);
+        }
+
+        self.defaults.resize(group_idx, Vec::new());
+        self.defaults.push(defaults);
+        self.tooltips.resize(group_idx, Vec::new());
+        self.tooltips.push(tooltips);
+        self.groups.push(Group {
+            label: label.into(),
+            items,
+            states,
+            collapsed: None,
+            subtitle: None,
+            accent: None,
+            disabled: None,
+        });
         self
     }
 
-    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
-        self.prompt = prompt.into();
+    /// Like [`group`](Self::group) but with a description below the header.
+    ///
+    /// The subtitle takes a line of its own that the cursor skips.
+    pub fn group_with_subtitle(
+        mut self,
+        label: impl Into<String>,
+        subtitle: impl Into<String>,
+        items: Vec<T>,
+    ) -> Self {
+        self.groups
+            .push(Group::new(label, items).subtitle(subtitle));
         self
     }
 
-    pub fn report(mut self, val: bool) -> Self {
-        self.report = val;
+    /// Like [`group`](Self::group) but tints the header and the highlighted item.
+    ///
+    /// Helps telling groups apart in long lists. How the color is applied is up to the
+    /// theme; [`SimpleTheme`] ignores it.
+    pub fn group_with_color(
+        mut self,
+        label: impl Into<String>,
+        items: Vec<T>,
+        color: Color,
+    ) -> Self {
+        self.groups.push(Group::new(label, items).accent(color));
         self
     }
 
-    pub fn clear(mut self, val: bool) -> Self {
-        self.clear = val;
+    /// Adds a group that is shown but can't be selected from.
+    ///
+    /// The header is dimmed with the reason, and the cursor moves past the header and the
+    /// items. Items checked by [`defaults`](Self::defaults) stay checked.
+    pub fn group_disabled(
+        mut self,
+        label: impl Into<String>,
+        items: Vec<T>,
+        reason: impl Into<String>,
+    ) -> Self {
+        self.groups.push(Group::new(label, items).disabled(reason));
         self
     }
 
-    pub fn max_length(mut self, val: usize) -> Self {
-        self.max_length = Some(val);
+    /// Shows the lines returned by `f` above the header of a group.
+    ///
+    /// Meant for introductions longer than a [`group_with_subtitle`](Self::group_with_subtitle).
+    /// `f` is called once when the prompt starts. The lines count towards the page
+    /// height and the cursor skips them. Setting a prologue again for the same group
+    /// replaces it.
+    pub fn group_prologue<F>(mut self, group_idx: usize, f: F) -> Self
+    where
+        F: Fn() -> Vec<String> + 'a,
+    {
+        self.prologues.insert(group_idx, Arc::new(f));
         self
     }
-}
 
-impl<T: ToString> GroupMultiSelect<'_, T> {
-    pub fn interact(self) -> Result<Vec<Vec<usize>>> {
-        self.interact_on(&Term::stderr())
+    /// Like [`group`](Self::group) but collects the items from an iterator.
+    pub fn group_from_iter<I>(self, label: impl Into<String>, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.group(label, items.into_iter().collect())
     }
 
-    pub fn interact_on(self, term: &Term) -> Result<Vec<Vec<usize>>> {
-        self._interact_on(term, false)?
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Cancelled").into())
+    /// Like [`group_with_states`](Self::group_with_states) but collects the items from an
+    /// iterator.
+    pub fn group_with_states_from_iter<I>(self, label: impl Into<String>, items: I) -> Self
+    where
+        I: IntoIterator<Item = (T, ItemState)>,
+    {
+        self.group_with_states(label, items.into_iter().collect())
     }
 
-    pub fn interact_opt(self) -> Result<Option<Vec<Vec<usize>>>> {
-        self.interact_on_opt(&Term::stderr())
+    /// Adds a group that starts collapsed or expanded regardless of
+    /// [`collapsed_by_default`](Self::collapsed_by_default).
+    pub fn group_collapsed(
+        mut self,
+        label: impl Into<String>,
+        items: Vec<T>,
+        collapsed: bool,
+    ) -> Self {
+        self.groups
+            .push(Group::new(label, items).collapsed(collapsed));
+        self
     }
 
-    pub fn interact_on_opt(self, term: &Term) -> Result<Option<Vec<Vec<usize>>>> {
-        self._interact_on(term, true)
+    /// Replaces the state of an item added before.
+    ///
+    /// Returns an error if there is no item at `item_idx` in the group at `group_idx`.
+    pub fn set_item_state(
+        mut self,
+        group_idx: usize,
+        item_idx: usize,
+        state: ItemState,
+    ) -> Result<Self> {
+        let Some(slot) = self
+            .groups
+            .get_mut(group_idx)
+            .and_then(|group| group.states.get_mut(item_idx))
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("No item {} in group {}", item_idx, group_idx),
+            )
+            .into());
+        };
+        let disables = !matches!(slot, ItemState::Disabled { .. })
+            && matches!(state, ItemState::Disabled { .. });
+        *slot = state;
+        if disables && self.auto_deselect_on_disable {
+            if let Some(default) = self
+                .defaults
+                .get_mut(group_idx)
+                .and_then(|defaults| defaults.get_mut(item_idx))
+            {
+                *default = false;
+            }
+        }
+        Ok(self)
     }
 
-    fn _interact_on(self, term: &Term, allow_quit: bool) -> Result<Option<Vec<Vec<usize>>>> {
-        if self.groups.is_empty() {
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No groups added").into());
-        }
+    pub fn defaults(mut self, defaults: Vec<Vec<bool>>) -> Self {
+        self.defaults = defaults;
+        self
+    }
 
-        let mut checked: Vec<Vec<bool>> = self
-            .groups
-            .iter()
-            .enumerate()
-            .map(|(g_idx, group)| {
-                (0..group.items.len())
-                    .map(|i_idx| {
-                        self.defaults
-                            .get(g_idx)
-                            .and_then(|g| g.get(i_idx))
-                            .copied()
-                            .unwrap_or(false)
-                    })
-                    .collect()
-            })
-            .collect();
+    /// Pre-fills the prompt with a previously returned [`Selection`].
+    ///
+    /// This is an alternative to [`defaults`](Self::defaults). A selection saved for
+    /// different groups doesn't fail: missing groups and items start unchecked and extra
+    /// entries are ignored.
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.defaults = selection.checked;
+        self
+    }
 
-        let mut cursor = Cursor::default();
-        let total_rows = self.total_rows();
+    /// Makes group headers selectable on their own.
+    ///
+    /// The header checkbox then shows a group-level flag that 'Space' toggles instead of
+    /// toggling all items of the group. The flags are returned in
+    /// [`SelectionOutcome::groups`]. The default is `false`.
+    pub fn group_selection(mut self, val: bool) -> Self {
+        self.group_selection = val;
+        self
+    }
 
-        if total_rows == 0 {
-            return Ok(Some(vec![vec![]; self.groups.len()]));
-        }
+    /// Sets the initial group-level flags used by [`group_selection`](Self::group_selection).
+    pub fn group_defaults(mut self, defaults: Vec<bool>) -> Self {
+        self.group_defaults = defaults;
+        self
+    }
 
-        let mut render = TermThemeRenderer::new(term, self.theme);
-        let mut page_offset = 0usize;
-        let capacity = self
-            .max_length
-            .unwrap_or(usize::MAX)
-            .min(term.size().0.saturating_sub(1) as usize);
+    /// Sets the tooltip text of items, indexed like [`defaults`](Self::defaults).
+    ///
+    /// Shown by [`focus_tooltip`](Self::focus_tooltip) while the item is focused.
+    pub fn tooltips(mut self, tooltips: Vec<Vec<Option<String>>>) -> Self {
+        self.tooltips = tooltips;
+        self
+    }
 
-        term.hide_cursor()?;
+    /// Reserves a line below the list that describes the focused item.
+    ///
+    /// The line shows the item's tooltip from [`tooltips`](Self::tooltips), falling back
+    /// to the warning message or disabled reason of the item, and is empty otherwise. It takes
+    /// one row from the page. The default is `false`.
+    pub fn focus_tooltip(mut self, val: bool) -> Self {
+        self.focus_tooltip = val;
+        self
+    }
 
-        loop {
-            self.render(&mut render, &checked, cursor, page_offset, capacity)?;
-
-            match term.read_key()? {
-                Key::ArrowDown | Key::Char('j') => {
-                    cursor = self.move_cursor_down(cursor);
-                    page_offset = self.adjust_page_offset(cursor, page_offset, capacity);
-                }
-                Key::ArrowUp | Key::Char('k') => {
-                    cursor = self.move_cursor_up(cursor);
-                    page_offset = self.adjust_page_offset(cursor, page_offset, capacity);
-                }
-                Key::Char(' ') => {
-                    self.toggle(&mut checked, cursor);
-                }
-                Key::Char('a') => {
-                    let all_selectable_selected = self
-                        .groups
-                        .iter()
-                        .zip(checked.iter())
-                        .flat_map(|(group, group_checked)| {
-                            group.states.iter().zip(group_checked.iter())
-                        })
-                        .filter(|(state, _)| !matches!(state, ItemState::Disabled { .. }))
-                        .all(|(_, &is_checked)| is_checked);
-                    let new_state = !all_selectable_selected;
-                    for (group, group_checked) in self.groups.iter().zip(checked.iter_mut()) {
-                        for (idx, state) in group.states.iter().enumerate() {
-                            if !matches!(state, ItemState::Disabled { .. }) {
-                                group_checked[idx] = new_state;
-                            }
+    /// Shows a line below the list that describes the key bindings.
+    ///
+    /// The line is generated from the [`KeyBindings`] set with
+    /// [`with_keybindings`](Self::with_keybindings) unless replaced with
+    /// [`help_text`](Self::help_text). It takes one row from the page. The default is
+    /// `false`.
+    pub fn show_help(mut self, val: bool) -> Self {
+        self.show_help = val;
+        self
+    }
+
+    /// Replaces the generated text of the [`show_help`](Self::show_help) line.
+    pub fn help_text(mut self, text: impl Into<String>) -> Self {
+        self.help_text = Some(text.into());
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    pub fn report(mut self, val: bool) -> Self {
+        self.report = val;
+        self
+    }
+
+    /// Prefixes the report with the number of selected items and groups.
+    ///
+    /// E.g. "Selected 3 of 7 items across 2 groups." Only has an effect with
+    /// [`report`](Self::report) enabled. The default is `false`.
+    pub fn report_counts(mut self, val: bool) -> Self {
+        self.report_counts = val;
+        self
+    }
+
+    /// Sets how a report that is wider than the terminal is shown.
+    ///
+    /// The default is [`ReportOverflow::Wrap`].
+    pub fn report_overflow(mut self, val: ReportOverflow) -> Self {
+        self.report_overflow = val;
+        self
+    }
+
+    /// Sets a message that is reported when the prompt is quit.
+    ///
+    /// With [`clear`](Self::clear) disabled the message ends up below the list, so the
+    /// transcript records that the prompt was cancelled. The default is `None`, which
+    /// quits silently.
+    pub fn report_on_quit(mut self, val: Option<String>) -> Self {
+        self.report_on_quit = val;
+        self
+    }
+
+    /// Returns the selection made so far when the prompt is quit.
+    ///
+    /// Normally [`interact_opt`](Self::interact_opt) and its variants return `None` when
+    /// the user quits. With this enabled they return `Some` with the unconfirmed selection
+    /// instead, e.g. to save a draft. The detailed variants tell both cases apart through
+    /// [`SelectionOutcome::cancelled`]. The default is `false`.
+    pub fn quit_returns_progress(mut self, val: bool) -> Self {
+        self.quit_returns_progress = val;
+        self
+    }
+
+    /// Writes the accepted selection as a single machine readable line to `sink`.
+    ///
+    /// This is independent of the human readable [`report`](Self::report), so the prompt
+    /// can stay on stderr while scripts read the selection from e.g. stdout. The line
+    /// lists every group with a selection as `group:item,item`, separated by `;`:
+    ///
+    /// ```text
+    /// line      = [ group *( ";" group ) ] "\n"
+    /// group     = index ":" index *( "," index )
+    /// ```
+    ///
+    /// For example `0:0,1;2:3` selects the first two items of the first group and the
+    /// fourth item of the third group. Indices refer to the original item positions.
+    pub fn emit_machine_readable(mut self, sink: impl io::Write + 'a) -> Self {
+        self.machine_output = Some(Box::new(sink));
+        self
+    }
+
+    /// Writes the selection to `sink` every time it changes while the prompt runs.
+    ///
+    /// Each change produces one line in the format of
+    /// [`emit_machine_readable`](Self::emit_machine_readable) and flushes `sink`, so a
+    /// downstream process can react live. Only the interact methods write to `sink`.
+    pub fn live_output(mut self, sink: impl io::Write + 'a) -> Self {
+        self.live_output = Some(Box::new(sink));
+        self
+    }
+
+    /// Calls `f` for every checkbox that changes while the prompt runs.
+    ///
+    /// Toggling a group header reports each affected item. With
+    /// [`group_selection`](Self::group_selection) the group-level flag is reported with
+    /// `item_idx` set to `None`.
+    pub fn on_toggle<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&ToggleEvent) + 'a,
+    {
+        self.on_toggle = Some(Box::new(f));
+        self
+    }
+
+    /// Leaves out the group header rows, for prompts that only show items.
+    pub(crate) fn hide_headers(mut self) -> Self {
+        self.hide_headers = true;
+        self
+    }
+
+    pub fn clear(mut self, val: bool) -> Self {
+        self.clear = val;
+        self
+    }
+
+    /// Keeps every frame on screen instead of redrawing in place.
+    ///
+    /// Each redraw is printed below the previous one, so the output of every
+    /// render can be inspected when debugging a theme or the layout. This is a
+    /// diagnostic aid and not meant for normal use. Clearing on accept or quit
+    /// is still controlled by [`clear`](Self::clear).
+    ///
+    /// The default is `false`.
+    pub fn debug_no_clear(mut self, val: bool) -> Self {
+        self.debug_no_clear = val;
+        self
+    }
+
+    pub fn max_length(mut self, val: usize) -> Self {
+        self.max_length = Some(val);
+        self
+    }
+
+    /// Limits the list to a fraction of the terminal height.
+    ///
+    /// A page holds at most `fraction` of the terminal rows, but at least one row, so
+    /// other content stays visible and the list adapts to the terminal size. When
+    /// [`max_length`](Self::max_length) is set as well the smaller limit wins. Lists that
+    /// don't fit show the page indicator as usual.
+    pub fn max_height_fraction(mut self, fraction: f32) -> Self {
+        self.max_height_fraction = Some(fraction.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Sets whether the list is paged to fit the terminal.
+    ///
+    /// Without paging every row is rendered and a list taller than the terminal scrolls
+    /// into the scrollback; [`max_length`](Self::max_length) is ignored. Rows that
+    /// scrolled out of view can't be erased anymore, so [`clear`](Self::clear) only
+    /// reliably clears lists that fit the terminal. The default is `true`.
+    pub fn paging(mut self, val: bool) -> Self {
+        self.paging = val;
+        self
+    }
+
+    /// Unchecks items that become disabled.
+    ///
+    /// Applies when [`recompute_states_in_background`](Self::recompute_states_in_background)
+    /// or [`set_item_state`](Self::set_item_state) turn an enabled item into a disabled
+    /// one; [`set_item_state`](Self::set_item_state) clears the item's default, so it has
+    /// to come after [`defaults`](Self::defaults). Items that start disabled keep their
+    /// default. The default is `true`.
+    pub fn auto_deselect_on_disable(mut self, val: bool) -> Self {
+        self.auto_deselect_on_disable = val;
+        self
+    }
+
+    /// Lets 'Tab' and 'Shift+Tab' jump between items that can be toggled.
+    ///
+    /// The keys move to the next or previous enabled item, skipping headers, disabled
+    /// items and collapsed groups, and wrap around at the ends of the list. The default
+    /// is `false`.
+    pub fn item_tab(mut self, val: bool) -> Self {
+        self.item_tab = val;
+        self
+    }
+
+    /// Shows a "↑ more" or "↓ more" row when rows are scrolled out of view.
+    ///
+    /// The indicators take two rows of the page while the list doesn't fit, so the page
+    /// never grows beyond [`max_length`](Self::max_length) or the terminal. They aren't
+    /// shown with [`groups_per_page`](Self::groups_per_page). The default is `true`.
+    pub fn show_scroll_indicators(mut self, val: bool) -> Self {
+        self.show_scroll_indicators = val;
+        self
+    }
+
+    /// Keeps `n` rows visible above and below the cursor while scrolling.
+    ///
+    /// The list starts scrolling once the cursor comes within `n` rows of the edge of the
+    /// page instead of only at the edge. Near the ends of the list and on pages that are
+    /// too small the margin shrinks. The default is `0`.
+    pub fn scroll_off(mut self, n: usize) -> Self {
+        self.scroll_off = n;
+        self
+    }
+
+    /// Moves the cursor from the last row to the first one and back with 'Down' and 'Up'.
+    ///
+    /// Disabled items that the cursor skips are skipped when wrapping as well. The default
+    /// is `false`, the cursor stops at the ends of the list.
+    pub fn wrap_around(mut self, val: bool) -> Self {
+        self.wrap_around = val;
+        self
+    }
+
+    /// Pages the list by groups instead of rows.
+    ///
+    /// Each page shows `n` groups with their items, no matter how many rows they take,
+    /// and 'PageDown'/'PageUp' jump to the first group of the next or previous page.
+    /// Moving the cursor past the last group of a page turns the page. Replaces the
+    /// row-based [`paging`](Self::paging), so a page that is taller than the terminal
+    /// scrolls. A value of `0` is treated as `1`. The default is row-based paging.
+    pub fn groups_per_page(mut self, n: usize) -> Self {
+        self.groups_per_page = Some(n.max(1));
+        self
+    }
+
+    /// Marks items whose checked state differs from their default.
+    ///
+    /// The marker is rendered by the theme after the item text. The default is `false`.
+    pub fn mark_changes(mut self, val: bool) -> Self {
+        self.mark_changes = val;
+        self
+    }
+
+    /// Shows a review of the selected items before accepting.
+    ///
+    /// 'Enter' switches to a flat list of the selected items, where 'Space' unchecks or
+    /// re-checks them. A second 'Enter' accepts the selection and 'Esc' returns to the
+    /// full list with the changes kept. The default is `false`.
+    pub fn review_step(mut self, val: bool) -> Self {
+        self.review_step = val;
+        self
+    }
+
+    /// Requires at least `n` items to be selected across all groups before accepting.
+    ///
+    /// 'Enter' is refused with an error below the list until the minimum is met. The
+    /// default is no minimum.
+    pub fn min_selections(mut self, n: usize) -> Self {
+        self.min_selections = Some(n);
+        self
+    }
+
+    /// Allows at most `n` items to be selected across all groups.
+    ///
+    /// Checking another item is refused once the limit is reached, and select-all checks
+    /// items from top to bottom until it is. The default is no maximum.
+    pub fn max_selections(mut self, n: usize) -> Self {
+        self.max_selections = Some(n);
+        self
+    }
+
+    /// Like [`min_selections`](Self::min_selections) but for a single group.
+    ///
+    /// The header of a group that doesn't meet its limits is marked by the theme.
+    pub fn group_min(mut self, group_idx: usize, n: usize) -> Self {
+        self.group_min.insert(group_idx, n);
+        self
+    }
+
+    /// Like [`max_selections`](Self::max_selections) but for a single group.
+    pub fn group_max(mut self, group_idx: usize, n: usize) -> Self {
+        self.group_max.insert(group_idx, n);
+        self
+    }
+
+    /// Moves the cursor to the offending group when 'Enter' is refused.
+    ///
+    /// When the selection doesn't pass the checks run on 'Enter', the error is shown below
+    /// the list and, with this enabled, the header of the first group that caused it is
+    /// focused and scrolled into view. The default is `false`.
+    pub fn focus_on_violation(mut self, val: bool) -> Self {
+        self.focus_on_violation = val;
+        self
+    }
+
+    /// Sets whether the cursor stops on disabled items.
+    ///
+    /// The default is [`DisabledNav::Skip`].
+    pub fn disabled_navigation(mut self, val: DisabledNav) -> Self {
+        self.disabled_navigation = val;
+        self
+    }
+
+    /// Briefly highlights an item after it was toggled with 'Space'.
+    ///
+    /// The highlight is dropped by the first redraw once it was shown for a moment, which
+    /// is usually the next key press. The default is `false`.
+    pub fn toggle_flash(mut self, val: bool) -> Self {
+        self.toggle_flash = val;
+        self
+    }
+
+    /// Prefixes the items on the current page with a hint key that toggles them.
+    ///
+    /// Hints are assigned in order from `1`-`9` followed by the letters without
+    /// a built-in binding, so at most 26 items per page get one. Keys bound in
+    /// [`KeyBindings`] or as [`action_key`](Self::action_key) are skipped, and
+    /// disabled items get no hint. Hints follow the page, so they always map
+    /// to the items currently shown.
+    ///
+    /// The default is `false`.
+    pub fn quick_keys(mut self, val: bool) -> Self {
+        self.quick_keys = val;
+        self
+    }
+
+    /// Shows how many items are selected after the prompt, e.g. "3 selected".
+    ///
+    /// With [`max_selections`](Self::max_selections) the limit is shown as well. The
+    /// default is `false`.
+    pub fn show_count(mut self, val: bool) -> Self {
+        self.show_count = val;
+        self
+    }
+
+    /// Shows how many items of each group are selected in its header, e.g. "(1/2)".
+    ///
+    /// Disabled items are left out of both numbers, so a group reads as complete once
+    /// every item that can be selected is. Collapsed groups always show their counts.
+    /// The default is `true`.
+    pub fn show_group_counts(mut self, val: bool) -> Self {
+        self.show_group_counts = val;
+        self
+    }
+
+    /// Pads item labels to a common width so whatever follows them lines up.
+    ///
+    /// The width is that of the widest visible item across all groups, limited to what
+    /// fits the terminal. Markers and labels appended by the theme then form a column.
+    /// The default is `false`.
+    pub fn align_labels(mut self, val: bool) -> Self {
+        self.align_labels = val;
+        self
+    }
+
+    /// Requires 'Enter' to be pressed twice to accept a selection in the given case.
+    ///
+    /// The first 'Enter' only asks in the status line to press it again, which has to
+    /// happen within two seconds and without another key in between. By default one
+    /// 'Enter' accepts.
+    pub fn double_confirm(mut self, when: DoubleConfirmWhen) -> Self {
+        self.double_confirm = Some(when);
+        self
+    }
+
+    /// Asks to confirm the warnings of the selected items before accepting.
+    ///
+    /// When a selected item has a warning, 'Enter' lists the warnings in the status line
+    /// and waits for [`KeyBindings::accept_warning`] ('y' by default) to accept. 'n' or any
+    /// other key returns to the list. The default is `false`.
+    pub fn confirm_warnings(mut self, val: bool) -> Self {
+        self.confirm_warnings = val;
+        self
+    }
+
+    /// Sets whether checking a warning item with 'Space' has to be confirmed.
+    ///
+    /// With confirmation required, the warning is shown in the status line and the item
+    /// is only checked once [`KeyBindings::accept_warning`] ('y' by default) is pressed. Any
+    /// other key cancels. Unchecking never needs a confirmation. The default is [`WarningToggle::Silent`].
+    pub fn warning_toggle_behavior(mut self, val: WarningToggle) -> Self {
+        self.warning_toggle = val;
+        self
+    }
+
+    /// Lets the user move the focused item within its group with
+    /// [`KeyBindings::reorder_up`] and [`KeyBindings::reorder_down`] ('K' and 'J' by default).
+    ///
+    /// [`SelectionOutcome::order`] reports the final order. Selected indices keep
+    /// referring to the original positions, while callbacks that receive the checked
+    /// matrix see the current order. The default is `false`.
+    pub fn reorderable(mut self, val: bool) -> Self {
+        self.reorderable = val;
+        self
+    }
+
+    /// Decides which items take part in select-all.
+    ///
+    /// Select-all only changes items for which `f` returns `true`. By default every item
+    /// that isn't [`ItemState::Disabled`] is selectable.
+    pub fn select_all_selectable<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ItemState) -> bool + 'a,
+    {
+        self.select_all_selectable = Some(Arc::new(f));
+        self
+    }
+
+    /// Decides whether a selectable item counts as selected for select-all.
+    ///
+    /// `f` receives the item state and its checked flag. Select-all deselects when every
+    /// selectable item counts as selected and selects otherwise. By default the checked
+    /// flag is used as is.
+    pub fn select_all_selected<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ItemState, bool) -> bool + 'a,
+    {
+        self.select_all_selected = Some(Arc::new(f));
+        self
+    }
+
+    /// Validates every change of the selection made by toggling or select-all.
+    ///
+    /// `f` receives the checked matrix after the change. Returning `Err` reverts the change,
+    /// returning `Ok(Some(_))` keeps it. In both cases the message is shown in a status line
+    /// below the list until the next key press, e.g. "can't exceed 3 selections". One row
+    /// of the terminal is reserved for the status line.
+    pub fn validate_change<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[Vec<bool>]) -> std::result::Result<Option<String>, String> + 'a,
+    {
+        self.validate_change = Some(Arc::new(f));
+        self
+    }
+
+    /// Validates the selection before 'Enter' accepts it.
+    ///
+    /// `f` receives the checked matrix and can check any combination across groups.
+    /// Returning `Err` keeps the prompt open and shows the message below the list, like
+    /// the errors of [`min_selections`](Self::min_selections). It runs after the selection
+    /// limits are checked.
+    pub fn validate_with<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[Vec<bool>]) -> std::result::Result<(), String> + 'a,
+    {
+        self.validate_with = Some(Arc::new(f));
+        self
+    }
+
+    /// Hides items for which `f` returns `false`.
+    ///
+    /// `f` receives the group index, item index, item, item state and the current checked
+    /// matrix, and is consulted again after every key press, so visibility can react to the
+    /// selection. Hidden items are skipped by the cursor and not rendered, but keep their
+    /// checked state. Select-all only acts on the items that are shown, so filtering first
+    /// allows bulk selecting the matches. Group headers are always shown.
+    pub fn visible_when<F>(mut self, f: F) -> Self
+    where
+        F: Fn(usize, usize, &T, &ItemState, &[Vec<bool>]) -> bool + 'a,
+    {
+        self.visible_when = Some(Arc::new(f));
+        self
+    }
+
+    /// Formats item labels with `f` instead of [`ToString`].
+    ///
+    /// `f` receives the item and whether it is checked and focused, and is called on every
+    /// frame, so labels can carry annotations computed at display time. The report after
+    /// the prompt uses it as well. Searching still matches the plain item text.
+    pub fn format_item<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&T, bool, bool) -> String + 'a,
+    {
+        self.format_item = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets whether groups start collapsed to just their header.
+    ///
+    /// Groups added with [`group_collapsed`](Self::group_collapsed) keep their own setting.
+    /// Collapsed groups are expanded with [`KeyBindings::right`] and collapsed again with
+    /// [`KeyBindings::left`], unless [`KeyBindings::horizontal`] uses those keys for
+    /// checking items.
+    /// The default is `false`.
+    pub fn collapsed_by_default(mut self, val: bool) -> Self {
+        self.collapsed_by_default = val;
+        self
+    }
+
+    /// Expands the group under the cursor and collapses the others automatically.
+    ///
+    /// Only the focused group is expanded, so moving onto the header of a collapsed group
+    /// makes its items reachable and collapses the group that was left. The default is
+    /// `false`.
+    pub fn auto_expand_on_focus(mut self, val: bool) -> Self {
+        self.auto_expand_on_focus = val;
+        self
+    }
+
+    /// Sets whether disabled items start hidden.
+    ///
+    /// [`KeyBindings::hide_disabled`] toggles between hiding and showing disabled items. Hidden items keep their
+    /// checked state. The default is `false`.
+    pub fn hide_disabled_by_default(mut self, val: bool) -> Self {
+        self.hide_disabled = val;
+        self
+    }
+
+    /// Lets the items of a group cycle through labeled states instead of a checkbox.
+    ///
+    /// 'Space' moves an item to the next label, e.g. `off` → `on` → `auto`, and the
+    /// current label is rendered after the item. The first label means unselected, every
+    /// other label counts as selected. The chosen label indices are returned in
+    /// [`SelectionOutcome::levels`]. Toggling the header selects items with the second
+    /// label. Needs at least two labels to have an effect.
+    pub fn cycle_labels(mut self, group_idx: usize, labels: Vec<String>) -> Self {
+        if self.cycle_labels.len() <= group_idx {
+            self.cycle_labels.resize(group_idx + 1, Vec::new());
+        }
+        self.cycle_labels[group_idx] = labels;
+        self
+    }
+
+    /// Makes the given groups mutually exclusive.
+    ///
+    /// Selecting an item in one of the groups clears the selection of the others, e.g. to
+    /// choose one backend out of several groups of backend options. Can be called
+    /// multiple times for independent sets. Select-all, invert and select-visible can check
+    /// items of several groups at once; afterwards the group under the cursor keeps its
+    /// selection, or else the first group of the set with a selection does.
+    pub fn exclusive_groups(mut self, groups: &[usize]) -> Self {
+        self.exclusive_groups.push(groups.to_vec());
+        self
+    }
+
+    /// Starts with the cursor on the given item.
+    ///
+    /// The item's group is expanded and the list is scrolled so the item is visible.
+    /// Out of range indices are clamped to the last group or item, and a disabled item
+    /// moves the cursor to the nearest enabled row.
+    pub fn reveal(mut self, group_idx: usize, item_idx: usize) -> Self {
+        self.reveal = Some(StartAt::Row(group_idx, Some(item_idx)));
+        self
+    }
+
+    /// Starts with the cursor on the given item, or on the group header if `item_idx` is
+    /// `None`.
+    ///
+    /// Like [`reveal`](Self::reveal), out of range indices are clamped and a disabled item
+    /// moves the cursor to the nearest enabled row. The default is the first header.
+    pub fn initial_cursor(mut self, group_idx: usize, item_idx: Option<usize>) -> Self {
+        self.reveal = Some(StartAt::Row(group_idx, item_idx));
+        self
+    }
+
+    /// Starts with the cursor on the first checked item.
+    ///
+    /// The item is revealed like with [`reveal`](Self::reveal). Without checked items the
+    /// cursor starts on the first header.
+    pub fn focus_first_selected(mut self) -> Self {
+        self.reveal = Some(StartAt::FirstSelected);
+        self
+    }
+
+    /// Starts with the list scrolled so the given row is at the top.
+    ///
+    /// Together with [`reveal`](Self::reveal) this restores the view a user last saw.
+    /// The offset counts visible rows, headers included. It is moved as little as needed
+    /// to keep the cursor on the page and not to leave empty rows at the end.
+    pub fn initial_page_offset(mut self, offset: usize) -> Self {
+        self.initial_page_offset = Some(offset);
+        self
+    }
+
+    /// Draws a border around the list.
+    ///
+    /// The border spans the terminal width and takes two rows, which are reserved when
+    /// computing how many rows fit on a page. When [`max_length`](Self::max_length) is set
+    /// the box always has that many rows and scrolls its content. The default is `false`.
+    pub fn boxed(mut self, val: bool) -> Self {
+        self.boxed = val;
+        self
+    }
+
+    /// Highlights the header of the group containing the cursor.
+    ///
+    /// While an item is focused its group header is rendered by the theme's
+    /// [`format_group_multi_select_active_group_header`](Theme::format_group_multi_select_active_group_header),
+    /// which keeps the context visible in long groups. The default is `false`.
+    pub fn highlight_active_group(mut self, val: bool) -> Self {
+        self.highlight_active_group = val;
+        self
+    }
+
+    /// Sets the key bindings.
+    pub fn with_keybindings(mut self, keybindings: KeyBindings) -> Self {
+        self.keybindings = keybindings;
+        self
+    }
+
+    /// Sets the blank lines around the list.
+    ///
+    /// The default adds none.
+    pub fn spacing(mut self, spacing: SpacingConfig) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Lets the user type a search query after pressing [`KeyBindings::search`] ('/' by default).
+    ///
+    /// While searching, typed characters are added to the query shown in the prompt line,
+    /// 'Backspace' removes the last one and 'Esc' clears the query and leaves search mode.
+    /// Only items containing the query (ignoring case) are listed, along with the headers
+    /// of their groups; the selection still refers to the full item lists. The arrow keys,
+    /// 'Space' and 'Enter' keep working. The default is `false`.
+    pub fn search(mut self, val: bool) -> Self {
+        self.search = val;
+        self
+    }
+
+    /// Matches the search query against group labels as well as items.
+    ///
+    /// A group whose label contains the query keeps all of its items when filtering, so
+    /// users can search by category. The matched part of the label is highlighted by the
+    /// theme. Only has an effect together with [`search`](Self::search). The default is
+    /// `false`.
+    pub fn filter_matches_groups(mut self, val: bool) -> Self {
+        self.filter_matches_groups = val;
+        self
+    }
+
+    /// Sets what 'Enter' does while a group header is focused.
+    ///
+    /// With [`HeaderEnter::ToggleCollapse`], add a key to [`KeyBindings::confirm`] so the
+    /// selection can be accepted from a header as well. The default is
+    /// [`HeaderEnter::Confirm`].
+    pub fn header_enter(mut self, val: HeaderEnter) -> Self {
+        self.header_enter = val;
+        self
+    }
+
+    /// Runs `f` once when the prompt starts, before the first render.
+    ///
+    /// `f` receives the checked matrix seeded from the [`defaults`](Self::defaults) and the
+    /// groups, and may change both, e.g. to apply remote state fetched lazily. Checked
+    /// rows are padded or truncated to match the groups afterwards.
+    pub fn on_start<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut Vec<Vec<bool>>, &mut Vec<Group<T>>) + 'a,
+    {
+        self.on_start = Some(Box::new(f));
+        self
+    }
+
+    /// Sets a key that ends the prompt to let the caller run an external action.
+    ///
+    /// E.g. 'F2' for "add a custom item". [`interact_with_action`](Self::interact_with_action)
+    /// reports the key as [`PromptOutcome::ActionRequested`] with the current selection.
+    /// The other interact methods handle it like quitting: the `_opt` methods return
+    /// `None` and the others fail with an [`io::ErrorKind::Interrupted`] error.
+    pub fn action_key(mut self, key: Key) -> Self {
+        self.action_key = Some(key);
+        self
+    }
+
+    /// Sets a second key that accepts the selection without finalizing it.
+    ///
+    /// For prompts that are one step of a wizard: [`interact_with_action`](Self::interact_with_action)
+    /// reports the key as [`PromptOutcome::Accepted`] with `finalize` set to `false`, and
+    /// 'Enter' sets it to `true`. What continuing means is up to the caller. The selection
+    /// is validated like on 'Enter' but not double confirmed or reviewed. The other
+    /// interact methods handle it like 'Enter'.
+    pub fn continue_key(mut self, key: Key) -> Self {
+        self.continue_key = Some(key);
+        self
+    }
+
+    /// Recomputes item states on a background thread whenever the selection changes.
+    ///
+    /// `f` receives a snapshot of the checked matrix and returns the new states for every
+    /// group. While it runs the prompt stays responsive and shows a busy indicator; the
+    /// result is applied and redrawn as soon as it arrives. Groups whose returned states
+    /// don't match their item count keep their current states. A result that is still
+    /// pending when the prompt is confirmed is discarded.
+    ///
+    /// Redrawing without a key press requires a [`KeySource`] that supports
+    /// [`read_key_timeout`](KeySource::read_key_timeout), like the [`TermKeySource`] the
+    /// `interact*` methods read from. A plain [`Term`] passed as the key source applies
+    /// the result on the next key press, and so does `interact_async_on` with the `async`
+    /// feature, which only redraws when the next key arrives from its stream.
+    ///
+    /// Recomputes run on one worker thread. If the selection changes again while `f` is
+    /// running, only the newest selection is computed next.
+    ///
+    /// Once a recompute was pending, the [`TermKeySource`] reads keys on a thread of its
+    /// own. That thread can't be interrupted while it waits for a key, so it outlives the
+    /// prompt and swallows the first key pressed after the prompt returned.
+    pub fn recompute_states_in_background<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[Vec<bool>]) -> Vec<Vec<ItemState>> + Send + Sync + 'static,
+    {
+        self.recompute_states = Some(Arc::new(f));
+        self
+    }
+}
+
+impl<'a, T: Clone> GroupMultiSelect<'a, T> {
+    /// Copies the configuration to build a variant of the prompt.
+    ///
+    /// Groups, defaults, callbacks and all other settings are copied. The copy shares
+    /// the theme reference, so it can't outlive the theme either. Output sinks set with
+    /// [`emit_machine_readable`](Self::emit_machine_readable) or
+    /// [`live_output`](Self::live_output) and the [`on_start`](Self::on_start) and
+    /// [`on_toggle`](Self::on_toggle) callbacks can only be used once and are left out.
+    pub fn clone_config(&self) -> Self {
+        GroupMultiSelect {
+            groups: self.groups.clone(),
+            defaults: self.defaults.clone(),
+            group_selection: self.group_selection,
+            group_defaults: self.group_defaults.clone(),
+            tooltips: self.tooltips.clone(),
+            focus_tooltip: self.focus_tooltip,
+            show_help: self.show_help,
+            help_text: self.help_text.clone(),
+            prompt: self.prompt.clone(),
+            report: self.report,
+            report_counts: self.report_counts,
+            report_overflow: self.report_overflow,
+            report_on_quit: self.report_on_quit.clone(),
+            quit_returns_progress: self.quit_returns_progress,
+            machine_output: None,
+            live_output: None,
+            on_toggle: None,
+            clear: self.clear,
+            debug_no_clear: self.debug_no_clear,
+            hide_headers: self.hide_headers,
+            max_length: self.max_length,
+            max_height_fraction: self.max_height_fraction,
+            paging: self.paging,
+            groups_per_page: self.groups_per_page,
+            scroll_off: self.scroll_off,
+            show_scroll_indicators: self.show_scroll_indicators,
+            wrap_around: self.wrap_around,
+            item_tab: self.item_tab,
+            auto_deselect_on_disable: self.auto_deselect_on_disable,
+            mark_changes: self.mark_changes,
+            toggle_flash: self.toggle_flash,
+            quick_keys: self.quick_keys,
+            align_labels: self.align_labels,
+            show_count: self.show_count,
+            show_group_counts: self.show_group_counts,
+            review_step: self.review_step,
+            focus_on_violation: self.focus_on_violation,
+            min_selections: self.min_selections,
+            max_selections: self.max_selections,
+            group_min: self.group_min.clone(),
+            group_max: self.group_max.clone(),
+            double_confirm: self.double_confirm,
+            confirm_warnings: self.confirm_warnings,
+            warning_toggle: self.warning_toggle,
+            reorderable: self.reorderable,
+            select_all_selectable: self.select_all_selectable.clone(),
+            select_all_selected: self.select_all_selected.clone(),
+            validate_change: self.validate_change.clone(),
+            validate_with: self.validate_with.clone(),
+            visible_when: self.visible_when.clone(),
+            format_item: self.format_item.clone(),
+            prologues: self.prologues.clone(),
+            recompute_states: self.recompute_states.clone(),
+            on_start: None,
+            keybindings: self.keybindings.clone(),
+            spacing: self.spacing,
+            search: self.search,
+            filter_matches_groups: self.filter_matches_groups,
+            header_enter: self.header_enter,
+            action_key: self.action_key.clone(),
+            continue_key: self.continue_key.clone(),
+            boxed: self.boxed,
+            highlight_active_group: self.highlight_active_group,
+            collapsed_by_default: self.collapsed_by_default,
+            auto_expand_on_focus: self.auto_expand_on_focus,
+            hide_disabled: self.hide_disabled,
+            disabled_navigation: self.disabled_navigation,
+            reveal: self.reveal,
+            initial_page_offset: self.initial_page_offset,
+            cycle_labels: self.cycle_labels.clone(),
+            exclusive_groups: self.exclusive_groups.clone(),
+            theme: self.theme,
+        }
+    }
+}
+
+impl<'a, T: ToString> GroupMultiSelect<'a, T> {
+    pub fn interact(self) -> Result<Vec<Vec<usize>>> {
+        self.interact_on(&Term::stderr())
+    }
+
+    pub fn interact_on(self, term: &Term) -> Result<Vec<Vec<usize>>> {
+        self.interact_on_with(term, TermKeySource::new(term))
+    }
+
+    /// Like [`interact_on`](Self::interact_on) but reads keys from the given [`KeySource`].
+    ///
+    /// The prompt is still rendered on `term`. This allows using an alternative input
+    /// backend such as [`CrosstermKeySource`](crate::CrosstermKeySource).
+    pub fn interact_on_with(self, term: &Term, keys: impl KeySource) -> Result<Vec<Vec<usize>>> {
+        Ok(self.interact_outcome_on_with(term, keys)?.selection)
+    }
+
+    pub fn interact_opt(self) -> Result<Option<Vec<Vec<usize>>>> {
+        self.interact_on_opt(&Term::stderr())
+    }
+
+    pub fn interact_on_opt(self, term: &Term) -> Result<Option<Vec<Vec<usize>>>> {
+        self.interact_on_opt_with(term, TermKeySource::new(term))
+    }
+
+    /// Like [`interact_on_opt`](Self::interact_on_opt) but reads keys from the given [`KeySource`].
+    pub fn interact_on_opt_with(
+        self,
+        term: &Term,
+        keys: impl KeySource,
+    ) -> Result<Option<Vec<Vec<usize>>>> {
+        Ok(self
+            .interact_outcome_on_opt_with(term, keys)?
+            .map(|outcome| outcome.selection))
+    }
+
+    /// Like [`interact`](Self::interact) but returns a [`SelectionOutcome`].
+    pub fn interact_outcome(self) -> Result<SelectionOutcome> {
+        let term = Term::stderr();
+        self.interact_outcome_on_with(&term, TermKeySource::new(&term))
+    }
+
+    /// Like [`interact_opt`](Self::interact_opt) but returns a [`SelectionOutcome`].
+    pub fn interact_outcome_opt(self) -> Result<Option<SelectionOutcome>> {
+        let term = Term::stderr();
+        self.interact_outcome_on_opt_with(&term, TermKeySource::new(&term))
+    }
+
+    /// Like [`interact_on_with`](Self::interact_on_with) but returns a [`SelectionOutcome`].
+    pub fn interact_outcome_on_with(
+        mut self,
+        term: &Term,
+        keys: impl KeySource,
+    ) -> Result<SelectionOutcome> {
+        match self._interact_on(term, keys, false)? {
+            PromptOutcome::Accepted { selection, .. } => Ok(selection),
+            _ => Err(Self::cancelled()),
+        }
+    }
+
+    /// Like [`interact_on_opt_with`](Self::interact_on_opt_with) but returns a
+    /// [`SelectionOutcome`].
+    pub fn interact_outcome_on_opt_with(
+        mut self,
+        term: &Term,
+        keys: impl KeySource,
+    ) -> Result<Option<SelectionOutcome>> {
+        match self._interact_on(term, keys, true)? {
+            PromptOutcome::Accepted { selection, .. } => Ok(Some(selection)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Like [`interact_outcome_opt`](Self::interact_outcome_opt) but reports the
+    /// [`action_key`](Self::action_key).
+    pub fn interact_with_action(self) -> Result<PromptOutcome> {
+        self.interact_with_action_on(&Term::stderr())
+    }
+
+    /// Like [`interact_with_action`](Self::interact_with_action) but allows a specific
+    /// terminal to be set.
+    pub fn interact_with_action_on(mut self, term: &Term) -> Result<PromptOutcome> {
+        self._interact_on(term, TermKeySource::new(term), true)
+    }
+
+    /// Like [`interact_opt`](Self::interact_opt) but returns the selection even if the
+    /// prompt is quit.
+    ///
+    /// Returns the [`Selection`] and whether it was accepted. The selection can be passed
+    /// to [`with_selection`](Self::with_selection) to reopen the prompt where it was left.
+    pub fn interact_selection(self) -> Result<(Selection, bool)> {
+        self.interact_selection_on(&Term::stderr())
+    }
+
+    /// Like [`interact_selection`](Self::interact_selection) but allows a specific terminal
+    /// to be set.
+    pub fn interact_selection_on(mut self, term: &Term) -> Result<(Selection, bool)> {
+        self.quit_returns_progress = true;
+        match self._interact_on(term, TermKeySource::new(term), true)? {
+            PromptOutcome::Accepted { selection, .. } => {
+                Ok((selection.to_selection(), !selection.cancelled))
+            }
+            PromptOutcome::ActionRequested { selection } => Ok((selection.to_selection(), false)),
+            PromptOutcome::Cancelled => Err(Self::cancelled()),
+        }
+    }
+
+    /// Like [`interact`](Self::interact) but pairs each group label with its selected items.
+    ///
+    /// Every group is returned, including the ones without a selection.
+    pub fn interact_labeled(self) -> Result<Vec<(String, Vec<String>)>> {
+        self.interact_labeled_on(&Term::stderr())
+    }
+
+    /// Like [`interact_labeled`](Self::interact_labeled) but allows a specific terminal to
+    /// be set.
+    pub fn interact_labeled_on(mut self, term: &Term) -> Result<Vec<(String, Vec<String>)>> {
+        let PromptOutcome::Accepted {
+            selection: outcome, ..
+        } = self._interact_on(term, TermKeySource::new(term), false)?
+        else {
+            return Err(Self::cancelled());
+        };
+        Ok(self.labeled(&outcome))
+    }
+
+    /// Like [`interact`](Self::interact) but returns clones of the selected items.
+    ///
+    /// Items are grouped like the indices returned by [`interact`](Self::interact), in
+    /// their final order.
+    pub fn interact_values(self) -> Result<Vec<Vec<T>>>
+    where
+        T: Clone,
+    {
+        self.interact_values_on(&Term::stderr())
+    }
+
+    /// Like [`interact_values`](Self::interact_values) but allows a specific terminal to
+    /// be set.
+    pub fn interact_values_on(mut self, term: &Term) -> Result<Vec<Vec<T>>>
+    where
+        T: Clone,
+    {
+        let PromptOutcome::Accepted {
+            selection: outcome, ..
+        } = self._interact_on(term, TermKeySource::new(term), false)?
+        else {
+            return Err(Self::cancelled());
+        };
+        Ok(self.values(&outcome))
+    }
+
+    /// Like [`interact_opt`](Self::interact_opt) but returns clones of the selected items.
+    pub fn interact_values_opt(self) -> Result<Option<Vec<Vec<T>>>>
+    where
+        T: Clone,
+    {
+        self.interact_values_opt_on(&Term::stderr())
+    }
+
+    /// Like [`interact_values_opt`](Self::interact_values_opt) but allows a specific
+    /// terminal to be set.
+    pub fn interact_values_opt_on(mut self, term: &Term) -> Result<Option<Vec<Vec<T>>>>
+    where
+        T: Clone,
+    {
+        match self._interact_on(term, TermKeySource::new(term), true)? {
+            PromptOutcome::Accepted { selection, .. } => Ok(Some(self.values(&selection))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Like [`interact`](Self::interact) but returns the selected items with their group
+    /// label and state.
+    pub fn interact_detailed(self) -> Result<Vec<GroupSelection<T>>>
+    where
+        T: Clone,
+    {
+        self.interact_detailed_on(&Term::stderr())
+    }
+
+    /// Like [`interact_detailed`](Self::interact_detailed) but allows a specific terminal
+    /// to be set.
+    pub fn interact_detailed_on(mut self, term: &Term) -> Result<Vec<GroupSelection<T>>>
+    where
+        T: Clone,
+    {
+        let PromptOutcome::Accepted {
+            selection: outcome, ..
+        } = self._interact_on(term, TermKeySource::new(term), false)?
+        else {
+            return Err(Self::cancelled());
+        };
+        Ok(self.detailed(&outcome))
+    }
+
+    /// Like [`interact`](Self::interact) but also returns the unselected items.
+    ///
+    /// Returns the selected and the unselected indices per group. Disabled items the user
+    /// couldn't uncheck stay in the selected list, unchecked disabled items are in neither.
+    #[allow(clippy::type_complexity)]
+    pub fn interact_with_unselected(self) -> Result<(Vec<Vec<usize>>, Vec<Vec<usize>>)> {
+        self.interact_with_unselected_on(&Term::stderr())
+    }
+
+    /// Like [`interact_with_unselected`](Self::interact_with_unselected) but allows a
+    /// specific terminal to be set.
+    #[allow(clippy::type_complexity)]
+    pub fn interact_with_unselected_on(
+        mut self,
+        term: &Term,
+    ) -> Result<(Vec<Vec<usize>>, Vec<Vec<usize>>)> {
+        let PromptOutcome::Accepted {
+            selection: outcome, ..
+        } = self._interact_on(term, TermKeySource::new(term), false)?
+        else {
+            return Err(Self::cancelled());
+        };
+        let unselected = self.unselected(&outcome);
+        Ok((outcome.selection, unselected))
+    }
+
+    fn _interact_on(
+        &mut self,
+        term: &Term,
+        mut keys: impl KeySource,
+        allow_quit: bool,
+    ) -> Result<PromptOutcome> {
+        let mut term_size = term.size();
+        let mut state = self.start(term_size)?;
+        state.allow_quit = allow_quit;
+        if let Some(outcome) = self.empty_outcome(&state) {
+            return Ok(outcome);
+        }
+        let mut render = self.open_render(term)?;
+
+        // Last frame drawn, keys that don't change it skip the redraw
+        let mut drawn: Option<Vec<String>> = None;
+
+        loop {
+            Self::expire_flash(&mut state);
+            self.redraw(term, &mut render, &mut state, &mut term_size, &mut drawn)?;
+
+            let key = loop {
+                if state.recompute.is_none() {
+                    break Some(keys.read_key()?);
+                }
+                if self.poll_recompute(&mut state) {
+                    break None;
+                }
+                if let Some(key) = keys.read_key_timeout(RECOMPUTE_POLL_INTERVAL)? {
+                    break Some(key);
+                }
+            };
+            let Some(key) = key else {
+                continue;
+            };
+
+            if let Some(outcome) = self.apply_key(term, &mut render, &mut state, key, allow_quit)? {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Outcome of a prompt without any rows, which is accepted right away.
+    fn empty_outcome(&self, state: &State) -> Option<PromptOutcome> {
+        if self.total_rows(&state.view) != 0 {
+            return None;
+        }
+        Some(PromptOutcome::Accepted {
+            selection: SelectionOutcome {
+                selection: vec![vec![]; self.groups.len()],
+                was_modified: false,
+                order: vec![vec![]; self.groups.len()],
+                levels: vec![vec![]; self.groups.len()],
+                groups: self.initial_group_checked(),
+                cancelled: false,
+            },
+            finalize: true,
+        })
+    }
+
+    /// Creates the renderer for `term` and hides the cursor while the prompt runs.
+    ///
+    /// Frames are rendered with [`frame_lines`](Self::frame_lines) and only written
+    /// through this renderer.
+    fn open_render<'t>(&self, term: &'t Term) -> Result<TermThemeRenderer<'t>>
+    where
+        'a: 't,
+    {
+        let render = TermThemeRenderer::new(term, self.theme);
+        term.hide_cursor()?;
+        Ok(render)
+    }
+
+    /// Follows terminal resizes and draws the frame if it differs from `drawn`.
+    fn redraw(
+        &self,
+        term: &Term,
+        render: &mut TermThemeRenderer,
+        state: &mut State,
+        term_size: &mut (u16, u16),
+        drawn: &mut Option<Vec<String>>,
+    ) -> Result<()> {
+        if term.size() != *term_size {
+            *term_size = term.size();
+            self.resize(state, *term_size);
+            *drawn = None;
+        }
+
+        let frame = self.frame_lines(state)?;
+        if drawn.as_ref() != Some(&frame) {
+            if drawn.is_some() && !self.debug_no_clear {
+                render.clear()?;
+            }
+            render.write_lines(&frame)?;
+            *drawn = Some(frame);
+        }
+        Ok(())
+    }
+
+    /// Applies a key press and returns the outcome if it ended the prompt.
+    ///
+    /// When the prompt ends, the frame is cleared, the report is printed and the cursor is
+    /// shown again. Otherwise background recomputes are started and the toggle and live
+    /// output hooks are notified.
+    fn apply_key(
+        &mut self,
+        term: &Term,
+        render: &mut TermThemeRenderer,
+        state: &mut State,
+        key: Key,
+        allow_quit: bool,
+    ) -> Result<Option<PromptOutcome>> {
+        let checked_before = self
+            .recompute_states
+            .as_ref()
+            .map(|_| state.checked.clone());
+        let live_before = self.live_output.as_ref().map(|_| state.checked.clone());
+        let toggle_before = self.toggle_snapshot(state);
+
+        match self.handle_key(state, key, allow_quit) {
+            Step::Continue => {}
+            step @ (Step::Accept | Step::Apply) => {
+                if self.clear {
+                    render.clear()?;
+                }
+
+                if self.report {
+                    self.render_report(render, &state.checked, state.width)?;
+                }
+
+                term.show_cursor()?;
+                term.flush()?;
+
+                let outcome = self.build_outcome(state);
+                if let Some(ref mut sink) = self.machine_output {
+                    writeln!(sink, "{}", Self::selection_path(&outcome.selection))?;
+                    sink.flush()?;
+                }
+
+                return Ok(Some(PromptOutcome::Accepted {
+                    selection: outcome,
+                    finalize: matches!(step, Step::Accept),
+                }));
+            }
+            Step::Action => {
+                if self.clear {
+                    render.clear()?;
+                }
+
+                term.show_cursor()?;
+                term.flush()?;
+                return Ok(Some(PromptOutcome::ActionRequested {
+                    selection: self.build_outcome(state),
+                }));
+            }
+            Step::Quit => {
+                if self.clear {
+                    render.clear()?;
+                }
+
+                if let Some(ref message) = self.report_on_quit {
+                    render.group_multi_select_quit_report(&self.prompt, message)?;
+                }
+
+                term.show_cursor()?;
+                term.flush()?;
+                if self.quit_returns_progress {
+                    return Ok(Some(PromptOutcome::Accepted {
+                        selection: self.progress(state),
+                        finalize: false,
+                    }));
+                }
+                return Ok(Some(PromptOutcome::Cancelled));
+            }
+        }
+
+        self.spawn_recompute(state, checked_before);
+        if let Some(before) = toggle_before {
+            self.emit_toggles(state, &before);
+        }
+        if let Some(before) = live_before {
+            self.emit_live(state, &before)?;
+        }
+        Ok(None)
+    }
+
+    /// Checkboxes to compare against after a key press, if [`on_toggle`](Self::on_toggle)
+    /// is set.
+    fn toggle_snapshot(&self, state: &State) -> Option<(Vec<Vec<bool>>, Vec<bool>)> {
+        self.on_toggle
+            .as_ref()
+            .map(|_| (state.checked.clone(), state.group_checked.clone()))
+    }
+
+    /// Reports the checkboxes that changed since `before` to [`on_toggle`](Self::on_toggle).
+    fn emit_toggles(&mut self, state: &State, before: &(Vec<Vec<bool>>, Vec<bool>)) {
+        let Some(ref mut f) = self.on_toggle else {
+            return;
+        };
+        let (checked_before, group_checked_before) = before;
+        for (group_idx, (checked, order)) in state.checked.iter().zip(&state.order).enumerate() {
+            if group_checked_before.get(group_idx) != state.group_checked.get(group_idx) {
+                f(&ToggleEvent {
+                    group_idx,
+                    item_idx: None,
+                    now_checked: state.group_checked[group_idx],
+                });
+            }
+            let Some(checked_before) = checked_before.get(group_idx) else {
+                continue;
+            };
+            for (pos, (&now_checked, &was_checked)) in
+                checked.iter().zip(checked_before).enumerate()
+            {
+                if now_checked != was_checked {
+                    f(&ToggleEvent {
+                        group_idx,
+                        item_idx: Some(order[pos]),
+                        now_checked,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Writes the selection to the [`live_output`](Self::live_output) if it changed.
+    fn emit_live(&mut self, state: &State, before: &[Vec<bool>]) -> io::Result<()> {
+        if state.checked == before {
+            return Ok(());
+        }
+        let selection = self.build_outcome(state).selection;
+        if let Some(ref mut sink) = self.live_output {
+            writeln!(sink, "{}", Self::selection_path(&selection))?;
+            sink.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Number of lines the first frame occupies when `capacity` rows fit on a page.
+    ///
+    /// Counts the prompt line, the visible headers and items, the box border and padding,
+    /// the [`spacing`](Self::spacing) and the tooltip line. Items hidden by
+    /// [`visible_when`](Self::visible_when) and [`group_prologue`](Self::group_prologue)
+    /// lines are only known once the prompt started, so the former are counted as shown
+    /// and the latter not at all.
+    pub fn rendered_height(&self, capacity: usize) -> usize {
+        let capacity = capacity.max(1);
+        let view = View::default();
+        let layout = match self.initial_collapsed(&view) {
+            Some(collapsed) => self.layout_with(&view, |group_idx| {
+                !self.hide_headers && collapsed[group_idx]
+            }),
+            None => self.layout(&view),
+        };
+        let mut rows = match self.groups_per_page {
+            Some(_) => self.page_end(&layout, 0, capacity).1,
+            None if self.has_scroll_indicators(&layout, capacity) => {
+                self.page_end(&layout, 0, capacity).1 + 1
+            }
+            None => layout.lines(0..layout.total_rows()).min(capacity),
+        };
+        if self.boxed {
+            if self.max_length.is_some() && self.paging {
+                rows = capacity;
+            }
+            rows += 2;
+        }
+        1 + rows + self.spacing.lines() + self.focus_tooltip as usize + self.show_help as usize
+    }
+
+    /// Hashes the selected items by group label and item text.
+    ///
+    /// `selection` holds item indices per group as returned by [`interact`](Self::interact),
+    /// so call this on the prompt before interacting or on a
+    /// [`clone_config`](Self::clone_config) of it. The hash doesn't depend on the order
+    /// of groups or items and is the same across runs and platforms, so it can key a
+    /// cache. Indices without a matching item are ignored.
+    pub fn selection_hash(&self, selection: &[Vec<usize>]) -> u64 {
+        let mut pairs: Vec<(&str, String)> = self
+            .groups
+            .iter()
+            .zip(selection)
+            .flat_map(|(group, indices)| {
+                indices.iter().filter_map(move |&idx| {
+                    let item = group.items.get(idx)?;
+                    Some((group.label.as_str(), item.to_string()))
+                })
+            })
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+
+        let mut hash = FNV_OFFSET_BASIS;
+        for (label, text) in pairs {
+            for part in [label.as_bytes(), text.as_bytes()] {
+                for &byte in part.iter().chain(&[0xff]) {
+                    hash ^= u64::from(byte);
+                    hash = hash.wrapping_mul(FNV_PRIME);
+                }
+            }
+        }
+        hash
+    }
+
+    /// Turns the prompt into a component that is driven by the caller.
+    ///
+    /// Instead of owning a terminal, the component renders each frame into a [`Frame`]
+    /// and receives keys through [`handle_key`](GroupMultiSelectComponent::handle_key).
+    /// This allows embedding the prompt in a larger TUI. `term_size` is the
+    /// `(rows, columns)` area available to the prompt.
+    pub fn component(mut self, term_size: (u16, u16)) -> Result<GroupMultiSelectComponent<'a, T>> {
+        let state = self.start(term_size)?;
+        Ok(GroupMultiSelectComponent {
+            prompt: self,
+            state,
+        })
+    }
+
+    /// Runs the prompt against a list of key events without a terminal.
+    ///
+    /// The keys go through the same state machine as [`interact_opt`](Self::interact_opt),
+    /// so this returns what an interactive session with the same key presses would return.
+    /// Background recomputes finish before the next key is applied. Returns an error if
+    /// the events run out before the selection is accepted or the prompt is quit.
+    pub fn run_with_events(self, events: Vec<Key>) -> Result<Option<Vec<Vec<usize>>>> {
+        let mut component = self.component(HEADLESS_TERM_SIZE)?;
+        component.run_keys(events).ok_or_else(Self::events_ended)
+    }
+
+    /// Runs the prompt against key presses without a terminal and accepts the selection
+    /// once they run out.
+    ///
+    /// Like [`run_with_events`](Self::run_with_events), but the selection doesn't need to be
+    /// accepted explicitly, which keeps scripted key sequences short in tests. No key is
+    /// pressed at the end: the selection is returned as it is, so a
+    /// [`review_step`](Self::review_step), [`double_confirm`](Self::double_confirm) or
+    /// [`header_enter`](Self::header_enter) doesn't need extra keys. Returns an error if
+    /// the keys run out while the selection doesn't pass the checks run on 'Enter'.
+    pub fn interact_with_keys(
+        self,
+        keys: impl IntoIterator<Item = Key>,
+    ) -> Result<Option<Vec<Vec<usize>>>> {
+        let mut component = self.component(HEADLESS_TERM_SIZE)?;
+        if let Some(result) = component.run_keys(keys) {
+            return Ok(result);
+        }
+        if component.prompt.refuse_accept(&mut component.state) {
+            return Err(Self::events_ended());
+        }
+        Ok(Some(component.outcome().selection))
+    }
+
+    /// Error returned by the interact methods that can't report a quit or the
+    /// [`action_key`](Self::action_key).
+    fn cancelled() -> crate::Error {
+        io::Error::new(io::ErrorKind::Interrupted, "Cancelled").into()
+    }
+
+    /// Error returned when scripted key presses run out before the prompt finished.
+    fn events_ended() -> crate::Error {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Events ended before the prompt finished",
+        )
+        .into()
+    }
+
+    /// Like [`interact_opt`](Self::interact_opt) but reads key presses from an async stream.
+    ///
+    /// Waiting for the next key doesn't block the thread, so the prompt can run inside an
+    /// async runtime. Frames are still written to the terminal synchronously. Returns an
+    /// error if the stream ends before the selection is accepted or the prompt is quit.
+    ///
+    /// Requires the `async` feature, which is off by default.
+    #[cfg(feature = "async")]
+    pub async fn interact_async<S>(self, keys: S) -> Result<Option<Vec<Vec<usize>>>>
+    where
+        S: Stream<Item = Key> + Unpin,
+    {
+        self.interact_async_on(&Term::stderr(), keys).await
+    }
+
+    /// Like [`interact_async`](Self::interact_async) but renders on the given terminal.
+    #[cfg(feature = "async")]
+    pub async fn interact_async_on<S>(
+        mut self,
+        term: &Term,
+        mut keys: S,
+    ) -> Result<Option<Vec<Vec<usize>>>>
+    where
+        S: Stream<Item = Key> + Unpin,
+    {
+        let mut term_size = term.size();
+        let mut state = self.start(term_size)?;
+        let outcome = match self.empty_outcome(&state) {
+            Some(outcome) => outcome,
+            None => {
+                let mut render = self.open_render(term)?;
+                let mut drawn: Option<Vec<String>> = None;
+                loop {
+                    self.poll_recompute(&mut state);
+                    Self::expire_flash(&mut state);
+                    self.redraw(term, &mut render, &mut state, &mut term_size, &mut drawn)?;
+
+                    let Some(key) = Self::next_key(&mut keys).await else {
+                        if self.clear {
+                            render.clear()?;
                         }
+                        term.show_cursor()?;
+                        term.flush()?;
+                        return Err(Self::stream_ended());
+                    };
+                    if let Some(outcome) =
+                        self.apply_key(term, &mut render, &mut state, key, true)?
+                    {
+                        break outcome;
+                    }
+                }
+            }
+        };
+
+        Ok(match outcome {
+            PromptOutcome::Accepted { selection, .. } => Some(selection.selection),
+            PromptOutcome::Cancelled | PromptOutcome::ActionRequested { .. } => None,
+        })
+    }
+
+    /// Like [`run_with_events`](Self::run_with_events) but reads key presses from an async
+    /// stream.
+    ///
+    /// Requires the `async` feature, which is off by default.
+    #[cfg(feature = "async")]
+    pub async fn run_with_stream<S>(self, mut keys: S) -> Result<Option<Vec<Vec<usize>>>>
+    where
+        S: Stream<Item = Key> + Unpin,
+    {
+        let mut component = self.component(HEADLESS_TERM_SIZE)?;
+
+        loop {
+            let Some(key) = Self::next_key(&mut keys).await else {
+                return Err(Self::stream_ended());
+            };
+            if let Some(selection) = component.run_key(key) {
+                return Ok(selection);
+            }
+        }
+    }
+
+    /// Waits for the next key of an async stream.
+    #[cfg(feature = "async")]
+    async fn next_key<S>(keys: &mut S) -> Option<Key>
+    where
+        S: Stream<Item = Key> + Unpin,
+    {
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut *keys).poll_next(cx)).await
+    }
+
+    /// Error returned when the key stream ends before the prompt finished.
+    #[cfg(feature = "async")]
+    fn stream_ended() -> crate::Error {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Key stream ended before the prompt finished",
+        )
+        .into()
+    }
+
+    /// Creates the interaction state for a terminal of the given size.
+    fn start(&mut self, term_size: (u16, u16)) -> Result<State> {
+        if self.groups.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No groups added").into());
+        }
+
+        let mut checked = self.initial_checked();
+        if let Some(f) = self.on_start.take() {
+            f(&mut checked, &mut self.groups);
+            if self.groups.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "No groups added").into());
+            }
+            checked.resize(self.groups.len(), Vec::new());
+            for (group_checked, group) in checked.iter_mut().zip(self.groups.iter()) {
+                group_checked.resize(group.items.len(), false);
+                for (is_checked, state) in group_checked.iter_mut().zip(group.states.iter()) {
+                    *is_checked |= *state == ItemState::Locked;
+                }
+            }
+        }
+
+        let mut state = self.new_state(checked, term_size);
+        if let Some(collapsed) = self.initial_collapsed(&state.view) {
+            for (group, collapsed) in self.groups.iter_mut().zip(collapsed) {
+                group.collapsed = Some(collapsed);
+            }
+        }
+        state.cursor = self.validate_cursor(&state.view, state.cursor);
+        let target = match self.reveal {
+            Some(StartAt::Row(group_idx, item_idx)) => Some((group_idx, item_idx)),
+            Some(StartAt::FirstSelected) => {
+                state
+                    .checked
+                    .iter()
+                    .enumerate()
+                    .find_map(|(group_idx, group_checked)| {
+                        let item_idx = group_checked.iter().position(|&c| c)?;
+                        Some((group_idx, Some(item_idx)))
+                    })
+            }
+            None => None,
+        };
+        if let Some((group_idx, item_idx)) = target {
+            self.reveal_item(&mut state, group_idx, item_idx);
+        }
+        if let Some(offset) = self.initial_page_offset {
+            let offset = offset.min(self.total_rows(&state.view).saturating_sub(1));
+            state.page_offset =
+                self.adjust_page_offset(&state.view, state.cursor, offset, state.capacity);
+        }
+        Ok(state)
+    }
+
+    /// Applies a finished background recompute.
+    ///
+    /// Returns `true` if the pending recompute finished and the list needs a redraw.
+    fn poll_recompute(&mut self, state: &mut State) -> bool {
+        let (Some(pending), Some(worker)) = (state.recompute, &state.recompute_worker) else {
+            return false;
+        };
+        let states = loop {
+            match worker.results.try_recv() {
+                Ok((id, states)) if id == pending => break Some(states),
+                // Result of a selection that changed again since
+                Ok(_) => {}
+                Err(mpsc::TryRecvError::Empty) => return false,
+                Err(mpsc::TryRecvError::Disconnected) => break None,
+            }
+        };
+        state.recompute = None;
+        let Some(states) = states else {
+            state.recompute_worker = None;
+            return true;
+        };
+        self.apply_states(states, &mut state.checked);
+        self.refresh_hidden(&mut state.view, &state.checked);
+        state.cursor = self.validate_cursor(&state.view, state.cursor);
+        state.page_offset =
+            self.adjust_page_offset(&state.view, state.cursor, state.page_offset, state.capacity);
+        true
+    }
+
+    /// Starts a background recompute if the selection changed since `before`.
+    fn spawn_recompute(&self, state: &mut State, before: Option<Vec<Vec<bool>>>) {
+        if let (Some(f), Some(before)) = (&self.recompute_states, before) {
+            if before != state.checked {
+                let worker = state
+                    .recompute_worker
+                    .get_or_insert_with(|| RecomputeWorker::spawn(Arc::clone(f)));
+                state.recompute = Some(worker.send(state.checked.clone()));
+            }
+        }
+    }
+
+    fn new_state(&self, checked: Vec<Vec<bool>>, term_size: (u16, u16)) -> State {
+        let group_checked = self.initial_group_checked();
+        let levels = checked
+            .iter()
+            .map(|g| g.iter().map(|&c| c as usize).collect())
+            .collect();
+        let mut view = View {
+            prologue_lines: (0..self.groups.len())
+                .map(|group_idx| {
+                    self.prologues
+                        .get(&group_idx)
+                        .map_or_else(Vec::new, |f| f())
+                })
+                .collect(),
+            ..View::default()
+        };
+        self.refresh_hidden(&mut view, &checked);
+        State {
+            view,
+            initial: checked.clone(),
+            checked,
+            initial_group_checked: group_checked.clone(),
+            group_checked,
+            order: self
+                .groups
+                .iter()
+                .map(|g| (0..g.items.len()).collect())
+                .collect(),
+            levels,
+            cursor: Cursor::default(),
+            page_offset: 0,
+            capacity: self.capacity(term_size),
+            width: term_size.1 as usize,
+            recompute: None,
+            recompute_worker: None,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            status: None,
+            error: None,
+            review: None,
+            pending_warning: None,
+            pending_accept: None,
+            acknowledged: self
+                .groups
+                .iter()
+                .map(|g| vec![false; g.items.len()])
+                .collect(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            query: None,
+            confirm_since: None,
+            flash: None,
+            allow_quit: true,
+        }
+    }
+
+    /// Whether each group starts collapsed with [`auto_expand_on_focus`](Self::auto_expand_on_focus).
+    ///
+    /// Only the group the cursor starts in is expanded. `None` without auto-expansion, when
+    /// the groups keep their own setting.
+    fn initial_collapsed(&self, view: &View) -> Option<Vec<bool>> {
+        if !self.auto_expand_on_focus {
+            return None;
+        }
+        let focused = self.validate_cursor(view, Cursor::default()).group_idx;
+        Some(
+            (0..self.groups.len())
+                .map(|group_idx| group_idx != focused)
+                .collect(),
+        )
+    }
+
+    /// Expands the group of the given item and moves the cursor into view on it.
+    ///
+    /// With `item_idx` being `None` the cursor is put on the group header instead.
+    fn reveal_item(&mut self, state: &mut State, group_idx: usize, item_idx: Option<usize>) {
+        let group_idx = group_idx.min(self.groups.len() - 1);
+        let group = &mut self.groups[group_idx];
+        let item_idx = item_idx
+            .filter(|_| !group.items.is_empty())
+            .map(|item_idx| item_idx.min(group.items.len() - 1));
+        if item_idx.is_some() {
+            group.collapsed = Some(false);
+        }
+
+        state.cursor = self.validate_cursor(
+            &state.view,
+            Cursor {
+                group_idx,
+                item_idx,
+            },
+        );
+        state.page_offset = self.adjust_page_offset(&state.view, state.cursor, 0, state.capacity);
+    }
+
+    /// Number of list rows that fit into a terminal of the given size.
+    fn capacity(&self, term_size: (u16, u16)) -> usize {
+        if !self.paging {
+            return usize::MAX;
+        }
+
+        // One row is taken by the prompt line
+        let mut available = (term_size.0 as usize).saturating_sub(1 + self.spacing.lines());
+        if self.boxed {
+            available = available.saturating_sub(2);
+        }
+        if self.shows_status() {
+            available = available.saturating_sub(1);
+        }
+        if self.checks_accept() {
+            available = available.saturating_sub(1);
+        }
+        if self.focus_tooltip {
+            available = available.saturating_sub(1);
+        }
+        if self.show_help {
+            available = available.saturating_sub(1);
+        }
+        if let Some(fraction) = self.max_height_fraction {
+            available = available.min((term_size.0 as f32 * fraction) as usize);
+        }
+        // Always show at least the focused row, even if the terminal reports no height
+        self.max_length.unwrap_or(usize::MAX).min(available).max(1)
+    }
+
+    /// Adapts the state to a new `(rows, columns)` terminal size.
+    fn resize(&self, state: &mut State, term_size: (u16, u16)) {
+        state.capacity = self.capacity(term_size);
+        state.width = term_size.1 as usize;
+        state.page_offset =
+            self.adjust_page_offset(&state.view, state.cursor, state.page_offset, state.capacity);
+    }
+
+    /// Renders the current state into lines instead of the terminal.
+    fn frame_lines(&self, state: &State) -> Result<Vec<String>> {
+        let mut render = TermThemeRenderer::buffered(self.theme);
+        if self.boxed {
+            render.set_box_width(Some(Self::box_inner_width(state.width)));
+        }
+        self.render(&mut render, state)?;
+        Ok(render.into_lines())
+    }
+
+    /// Width available to a row inside the box border.
+    fn box_inner_width(width: usize) -> usize {
+        // Leave the last column free to avoid auto-wrapping, plus two columns per side
+        width.saturating_sub(5)
+    }
+
+    /// Applies a single key press to the interaction state.
+    fn handle_key(&mut self, state: &mut State, key: Key, allow_quit: bool) -> Step {
+        state.status = None;
+        state.error = None;
+
+        if state.review.is_some() {
+            return self.handle_review_key(state, key);
+        }
+
+        let (key, confirmed) = match state.pending_warning.take() {
+            Some(_) if self.keybindings.accept_warning.contains(&key) => (Key::Char(' '), true),
+            Some(_) => return Step::Continue,
+            None => (key, false),
+        };
+        let (key, warnings_confirmed) = match state.pending_accept.take() {
+            Some(accept) if self.keybindings.accept_warning.contains(&key) => (accept, true),
+            Some(_) => return Step::Continue,
+            None => (key, false),
+        };
+
+        if let Some(ref mut query) = state.query {
+            let edited = match key {
+                Key::Char(c) if c != ' ' && !c.is_control() => {
+                    query.push(c);
+                    true
+                }
+                Key::Backspace => {
+                    query.pop();
+                    true
+                }
+                Key::Escape => {
+                    state.query = None;
+                    true
+                }
+                _ => false,
+            };
+            if edited {
+                self.refresh_filter(&mut state.view, state.query.as_deref());
+                state.cursor = self.validate_cursor(&state.view, state.cursor);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+                return Step::Continue;
+            }
+        }
+
+        let prev_group = state.cursor.group_idx;
+        let Some(key) = self.resolve_sequence(state, key) else {
+            self.auto_expand(state, prev_group);
+            return Step::Continue;
+        };
+
+        let mut toggle = confirmed || self.keybindings.toggle.contains(&key);
+        if let Key::Char(c) = key {
+            if let Some(&(_, target)) = self.quick_key_targets(state).iter().find(|t| t.0 == c) {
+                state.cursor = target;
+                toggle = true;
+            }
+        }
+
+        let confirm_pending = state
+            .confirm_since
+            .take()
+            .is_some_and(|since| since.elapsed() <= DOUBLE_CONFIRM_WINDOW);
+        let before = (state.checked.clone(), state.levels.clone());
+        let on_header = state.cursor.item_idx.is_none();
+        let apply = self.continue_key.as_ref() == Some(&key);
+        let header_collapse =
+            key == Key::Enter && on_header && self.header_enter == HeaderEnter::ToggleCollapse;
+        let confirm = self.keybindings.confirm.contains(&key) && !header_collapse;
+        let warnings = if confirm && self.confirm_warnings && !warnings_confirmed {
+            self.warning_summary(state)
+        } else {
+            None
+        };
+
+        match key {
+            _ if self.action_key.as_ref() == Some(&key) => return Step::Action,
+            _ if apply && self.refuse_accept(state) => {}
+            _ if apply => return Step::Apply,
+            _ if confirm && self.refuse_accept(state) => {}
+            _ if confirm && warnings.is_some() => {
+                let yes = self.accept_warning_hint();
+                state.status = warnings.map(|summary| format!("{}. Accept? ({}/n)", summary, yes));
+                state.pending_accept = Some(key.clone());
+            }
+            _ if confirm && !confirm_pending && self.needs_double_confirm(state) => {
+                state.confirm_since = Some(Instant::now());
+                state.status = Some("Press Enter again to confirm".to_string());
+            }
+            _ if confirm && self.review_step => {
+                let items: Vec<Cursor> = state
+                    .checked
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(group_idx, group_checked)| {
+                        group_checked
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, &is_checked)| is_checked)
+                            .map(move |(item_idx, _)| Cursor {
+                                group_idx,
+                                item_idx: Some(item_idx),
+                            })
+                    })
+                    .collect();
+                if items.is_empty() {
+                    return Step::Accept;
+                }
+                state.review = Some(Review { items, cursor: 0 });
+            }
+            _ if confirm => return Step::Accept,
+            UNDO_KEY => Self::restore(state, Undo::Undo),
+            REDO_KEY => Self::restore(state, Undo::Redo),
+            _ if header_collapse => {
+                let group_idx = state.cursor.group_idx;
+                self.groups[group_idx].collapsed = Some(!self.is_collapsed(group_idx));
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            _ if self.keybindings.move_down.contains(&key) => {
+                state.cursor = self.step_cursor(&state.view, state.cursor, true);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            _ if self.keybindings.move_up.contains(&key) => {
+                state.cursor = self.step_cursor(&state.view, state.cursor, false);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            _ if self.keybindings.horizontal == HorizontalKeys::Check
+                && (self.keybindings.left.contains(&key)
+                    || self.keybindings.right.contains(&key)) =>
+            {
+                let value = self.keybindings.right.contains(&key);
+                if self.group_selection && state.cursor.item_idx.is_none() {
+                    state.group_checked[state.cursor.group_idx] = value;
+                } else {
+                    let before = state.checked.clone();
+                    self.set_checked(&mut state.checked, state.cursor, value);
+                    self.check_change(state, before);
+                }
+            }
+            _ if self.keybindings.left.contains(&key) => {
+                let group_idx = state.cursor.group_idx;
+                self.groups[group_idx].collapsed = Some(true);
+                state.cursor = self.header_cursor(&state.view, group_idx);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            _ if self.keybindings.right.contains(&key) => {
+                self.groups[state.cursor.group_idx].collapsed = Some(false);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            Key::Tab | Key::BackTab if self.item_tab => {
+                state.cursor = self.next_item(&state.view, state.cursor, key == Key::Tab);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            Key::PageDown | Key::PageUp if self.groups_per_page.is_some() => {
+                let n = self.groups_per_page.unwrap_or(1);
+                let page = state.cursor.group_idx / n;
+                let target = if key == Key::PageDown {
+                    Some(page + 1).filter(|&page| page * n < self.groups.len())
+                } else {
+                    page.checked_sub(1)
+                };
+                if let Some(page) = target {
+                    state.cursor = Cursor {
+                        group_idx: page * n,
+                        item_idx: None,
+                    };
+                    state.page_offset = self.adjust_page_offset(
+                        &state.view,
+                        state.cursor,
+                        state.page_offset,
+                        state.capacity,
+                    );
+                }
+            }
+            Key::PageDown | Key::PageUp => {
+                let rows = self.rows_capacity(&self.layout(&state.view), state.capacity);
+                state.cursor =
+                    self.move_cursor_by(&state.view, state.cursor, rows, key == Key::PageDown);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            _ if self.reorderable
+                && (self.keybindings.reorder_up.contains(&key)
+                    || self.keybindings.reorder_down.contains(&key)) =>
+            {
+                if let Some(item_idx) = state.cursor.item_idx {
+                    let target = if self.keybindings.reorder_up.contains(&key) {
+                        item_idx.checked_sub(1)
+                    } else {
+                        Some(item_idx + 1)
+                    };
+                    if let Some(target) = target {
+                        self.swap_items(state, state.cursor.group_idx, item_idx, target);
+                    }
+                }
+            }
+            _ if toggle && self.group_selection && state.cursor.item_idx.is_none() => {
+                let flag = &mut state.group_checked[state.cursor.group_idx];
+                *flag = !*flag;
+            }
+            _ if self.search && self.keybindings.search.contains(&key) => {
+                state.query = Some(String::new())
+            }
+            _ if on_header && self.keybindings.clear_group.contains(&key) => {
+                let before = state.checked.clone();
+                self.set_checked(&mut state.checked, state.cursor, false);
+                self.check_change(state, before);
+            }
+            _ if toggle && !confirmed && self.needs_warning_confirm(state) => {
+                if let Some(ItemState::Warning { message }) = self.item_state(state.cursor) {
+                    let yes = self.accept_warning_hint();
+                    state.status = Some(format!("{} (press {} to confirm)", message, yes));
+                }
+                state.pending_warning = Some(state.cursor);
+            }
+            _ if toggle => {
+                if let (true, Some(item_idx)) = (confirmed, state.cursor.item_idx) {
+                    state.acknowledged[state.cursor.group_idx][item_idx] = true;
+                }
+                self.toggle_at(state, state.cursor);
+                if self.toggle_flash && state.cursor.item_idx.is_some() {
+                    state.flash = Some((state.cursor, Instant::now()));
+                }
+            }
+            _ if self.keybindings.select_all.contains(&key) => {
+                let before = state.checked.clone();
+                self.select_all(&state.view, &mut state.checked);
+                self.enforce_exclusive_sets(&mut state.checked, state.cursor.group_idx);
+                self.check_change(state, before);
+            }
+            _ if self.keybindings.select_visible.contains(&key) => {
+                let targets = self.page_targets(state);
+                let before = state.checked.clone();
+                self.toggle_all(&mut state.checked, &targets);
+                self.enforce_exclusive_sets(&mut state.checked, state.cursor.group_idx);
+                self.check_change(state, before);
+            }
+            _ if self.keybindings.select_group.contains(&key) => {
+                let before = state.checked.clone();
+                let header = Cursor {
+                    group_idx: state.cursor.group_idx,
+                    item_idx: None,
+                };
+                self.set_checked(&mut state.checked, header, true);
+                self.check_change(state, before);
+            }
+            _ if self.keybindings.invert.contains(&key) => {
+                let before = state.checked.clone();
+                self.invert(&state.view, &mut state.checked);
+                self.enforce_exclusive_sets(&mut state.checked, state.cursor.group_idx);
+                self.check_change(state, before);
+            }
+            _ if allow_quit && self.keybindings.quit.contains(&key) => return Step::Quit,
+            _ if self.keybindings.hide_disabled.contains(&key) => {
+                self.hide_disabled = !self.hide_disabled;
+                state.cursor = self.validate_cursor(&state.view, state.cursor);
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+            }
+            _ => {}
+        }
+
+        if key != UNDO_KEY
+            && key != REDO_KEY
+            && (state.checked != before.0 || state.levels != before.1)
+        {
+            if state.undo.len() == UNDO_LIMIT {
+                state.undo.remove(0);
+            }
+            state.undo.push(before);
+            state.redo.clear();
+        }
+
+        if self.visible_when.is_some() {
+            self.refresh_hidden(&mut state.view, &state.checked);
+            state.cursor = self.validate_cursor(&state.view, state.cursor);
+            state.page_offset = self.adjust_page_offset(
+                &state.view,
+                state.cursor,
+                state.page_offset,
+                state.capacity,
+            );
+        }
+        self.auto_expand(state, prev_group);
+
+        Step::Continue
+    }
+
+    /// Ends the highlight of a toggled item once it is shown long enough.
+    ///
+    /// Returns `true` if the highlight ended.
+    fn expire_flash(state: &mut State) -> bool {
+        match state.flash {
+            Some((_, since)) if since.elapsed() >= FLASH_DURATION => {
+                state.flash = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether accepting the current selection needs a second 'Enter'.
+    fn needs_double_confirm(&self, state: &State) -> bool {
+        let mut selected = self
+            .groups
+            .iter()
+            .zip(state.checked.iter())
+            .flat_map(|(group, checked)| group.states.iter().zip(checked.iter()))
+            .filter(|(_, &is_checked)| is_checked)
+            .map(|(item_state, _)| item_state);
+        match self.double_confirm {
+            None => false,
+            Some(DoubleConfirmWhen::Always) => true,
+            Some(DoubleConfirmWhen::WhenEmpty) => selected.next().is_none(),
+            Some(DoubleConfirmWhen::WhenWarnings) => {
+                selected.any(|item_state| matches!(item_state, ItemState::Warning { .. }))
+            }
+        }
+    }
+
+    /// Warnings of the selected items, `None` if no selected item has a warning.
+    fn warning_summary(&self, state: &State) -> Option<String> {
+        let warnings: Vec<String> = self
+            .groups
+            .iter()
+            .zip(state.checked.iter())
+            .flat_map(|(group, checked)| {
+                group
+                    .items
+                    .iter()
+                    .zip(group.states.iter())
+                    .zip(checked.iter())
+            })
+            .filter_map(|((item, item_state), &is_checked)| match item_state {
+                ItemState::Warning { message } if is_checked => {
+                    Some(format!("{}: {}", item.to_string(), message))
+                }
+                _ => None,
+            })
+            .collect();
+        (!warnings.is_empty()).then(|| warnings.join(", "))
+    }
+
+    /// Whether checking the focused item has to be confirmed first.
+    fn needs_warning_confirm(&self, state: &State) -> bool {
+        let (Some(item_idx), Some(ItemState::Warning { .. })) =
+            (state.cursor.item_idx, self.item_state(state.cursor))
+        else {
+            return false;
+        };
+        if state.checked[state.cursor.group_idx][item_idx] {
+            return false;
+        }
+        match self.warning_toggle {
+            WarningToggle::Silent => false,
+            WarningToggle::ConfirmEach => true,
+            WarningToggle::BlockUntilAcknowledged => {
+                !state.acknowledged[state.cursor.group_idx][item_idx]
+            }
+        }
+    }
+
+    /// State of the item under `cursor`, `None` for headers.
+    fn item_state(&self, cursor: Cursor) -> Option<&ItemState> {
+        cursor
+            .item_idx
+            .map(|item_idx| &self.groups[cursor.group_idx].states[item_idx])
+    }
+
+    /// Steps back or forward through the history of selection changes.
+    fn restore(state: &mut State, direction: Undo) {
+        let (from, to) = match direction {
+            Undo::Undo => (&mut state.undo, &mut state.redo),
+            Undo::Redo => (&mut state.redo, &mut state.undo),
+        };
+        if let Some((checked, levels)) = from.pop() {
+            to.push((
+                std::mem::replace(&mut state.checked, checked),
+                std::mem::replace(&mut state.levels, levels),
+            ));
+        }
+    }
+
+    /// Expands the focused group and collapses `prev_group` once the cursor left it.
+    fn auto_expand(&mut self, state: &mut State, prev_group: usize) {
+        if !self.auto_expand_on_focus || state.cursor.group_idx == prev_group {
+            return;
+        }
+        self.groups[prev_group].collapsed = Some(true);
+        self.groups[state.cursor.group_idx].collapsed = Some(false);
+        state.page_offset =
+            self.adjust_page_offset(&state.view, state.cursor, state.page_offset, state.capacity);
+    }
+
+    /// Checks whether the selection may be accepted.
+    ///
+    /// Returns the error message and the first offending group, if any.
+    fn check_accept(&self, state: &State) -> std::result::Result<(), (String, Option<usize>)> {
+        let total = Self::selected_count(&state.checked);
+        if let Some(min) = self.min_selections.filter(|&min| total < min) {
+            return Err((format!("Select at least {}", Self::count_items(min)), None));
+        }
+        if let Some(max) = self.max_selections.filter(|&max| total > max) {
+            return Err((format!("Select at most {}", Self::count_items(max)), None));
+        }
+        for (group_idx, group) in self.groups.iter().enumerate() {
+            let count = state.checked[group_idx].iter().filter(|&&c| c).count();
+            if let Some(&min) = self.group_min.get(&group_idx).filter(|&&min| count < min) {
+                let message = format!(
+                    "Select at least {} in {}",
+                    Self::count_items(min),
+                    group.label
+                );
+                return Err((message, Some(group_idx)));
+            }
+            if let Some(&max) = self.group_max.get(&group_idx).filter(|&&max| count > max) {
+                let message = format!(
+                    "Select at most {} in {}",
+                    Self::count_items(max),
+                    group.label
+                );
+                return Err((message, Some(group_idx)));
+            }
+        }
+        if let Some(ref f) = self.validate_with {
+            f(&state.checked).map_err(|message| (message, None))?;
+        }
+        Ok(())
+    }
+
+    /// Whether accepting the selection can be refused with an error.
+    /// Whether a status line can be shown below the list.
+    fn shows_status(&self) -> bool {
+        self.validate_change.is_some()
+            || self.double_confirm.is_some()
+            || self.confirm_warnings
+            || self.warning_toggle != WarningToggle::Silent
+    }
+
+    fn checks_accept(&self) -> bool {
+        self.validate_with.is_some()
+            || self.min_selections.is_some()
+            || self.max_selections.is_some()
+            || !self.group_min.is_empty()
+            || !self.group_max.is_empty()
+    }
+
+    fn selected_count(checked: &[Vec<bool>]) -> usize {
+        checked.iter().flatten().filter(|&&c| c).count()
+    }
+
+    /// "1 item" or "`n` items".
+    fn count_items(n: usize) -> String {
+        format!("{} item{}", n, if n == 1 { "" } else { "s" })
+    }
+
+    /// Whether the selection of a group meets its constraints.
+    fn group_within_limits(&self, group_idx: usize, checked: &[bool]) -> bool {
+        let count = checked.iter().filter(|&&c| c).count();
+        self.group_min
+            .get(&group_idx)
+            .map_or(true, |&min| count >= min)
+            && self
+                .group_max
+                .get(&group_idx)
+                .map_or(true, |&max| count <= max)
+    }
+
+    /// Whether one more item can be checked in a group without exceeding a maximum.
+    fn below_max(&self, checked: &[Vec<bool>], group_idx: usize) -> bool {
+        let group_count = checked[group_idx].iter().filter(|&&c| c).count();
+        self.max_selections
+            .map_or(true, |max| Self::selected_count(checked) < max)
+            && self
+                .group_max
+                .get(&group_idx)
+                .map_or(true, |&max| group_count < max)
+    }
+
+    /// Error shown when a change checked more items than a maximum allows.
+    fn exceeded_max(&self, before: &[Vec<bool>], checked: &[Vec<bool>]) -> Option<String> {
+        let total = Self::selected_count(checked);
+        if let Some(max) = self.max_selections {
+            if total > max && total > Self::selected_count(before) {
+                return Some(format!("Selection limit reached ({}/{})", max, max));
+            }
+        }
+        self.group_max.iter().find_map(|(&group_idx, &max)| {
+            let count = |checked: &[Vec<bool>]| checked[group_idx].iter().filter(|&&c| c).count();
+            (group_idx < checked.len() && count(checked) > max && count(checked) > count(before))
+                .then(|| {
+                    let label = &self.groups[group_idx].label;
+                    format!("Selection limit reached in {} ({}/{})", label, max, max)
+                })
+        })
+    }
+
+    /// Runs [`check_accept`](Self::check_accept) and reports a failure below the list.
+    ///
+    /// Returns `true` if accepting has to be refused.
+    fn refuse_accept(&mut self, state: &mut State) -> bool {
+        let Err((message, group_idx)) = self.check_accept(state) else {
+            return false;
+        };
+        state.error = Some(message);
+        if let (true, Some(group_idx)) = (self.focus_on_violation, group_idx) {
+            self.focus_group(state, group_idx);
+        }
+        true
+    }
+
+    /// Moves the cursor to the header of a group and scrolls it into view.
+    fn focus_group(&mut self, state: &mut State, group_idx: usize) {
+        state.cursor = self.header_cursor(&state.view, group_idx);
+        state.page_offset =
+            self.adjust_page_offset(&state.view, state.cursor, state.page_offset, state.capacity);
+    }
+
+    /// Applies a key press while the review screen is shown.
+    fn handle_review_key(&mut self, state: &mut State, key: Key) -> Step {
+        let Some(ref mut review) = state.review else {
+            return Step::Continue;
+        };
+
+        match key {
+            _ if self.keybindings.move_down.contains(&key) => {
+                review.cursor = (review.cursor + 1).min(review.items.len() - 1);
+            }
+            _ if self.keybindings.move_up.contains(&key) => {
+                review.cursor = review.cursor.saturating_sub(1);
+            }
+            _ if self.keybindings.toggle.contains(&key) => {
+                let cursor = review.items[review.cursor];
+                self.toggle_at(state, cursor);
+            }
+            _ if self.keybindings.confirm.contains(&key) && self.refuse_accept(state) => {
+                state.review = None
+            }
+            _ if self.keybindings.confirm.contains(&key) => return Step::Accept,
+            _ if self.keybindings.quit.contains(&key) => state.review = None,
+            _ => {}
+        }
+
+        Step::Continue
+    }
+
+    /// Swaps two items of a group along with their states, keeping the cursor on the
+    /// item at `a`.
+    fn swap_items(&mut self, state: &mut State, group_idx: usize, a: usize, b: usize) {
+        let group = &mut self.groups[group_idx];
+        if b >= group.items.len() {
+            return;
+        }
+        group.items.swap(a, b);
+        group.states.swap(a, b);
+        state.checked[group_idx].swap(a, b);
+        state.initial[group_idx].swap(a, b);
+        state.order[group_idx].swap(a, b);
+        state.levels[group_idx].swap(a, b);
+        state.acknowledged[group_idx].swap(a, b);
+        // Recorded matrices refer to the previous order
+        state.undo.clear();
+        state.redo.clear();
+        if let Some(tooltips) = self.tooltips.get_mut(group_idx) {
+            if a.max(b) < tooltips.len() {
+                tooltips.swap(a, b);
+            }
+        }
+        if let Some(hidden) = state.view.hidden.get_mut(group_idx) {
+            hidden.swap(a, b);
+        }
+        if let Some(filtered) = state.view.filtered.get_mut(group_idx) {
+            filtered.swap(a, b);
+        }
+
+        state.cursor.item_idx = Some(b);
+        state.page_offset =
+            self.adjust_page_offset(&state.view, state.cursor, state.page_offset, state.capacity);
+    }
+
+    /// Toggles the row under `cursor`, or moves a cycling item to its next label.
+    fn toggle_at(&self, state: &mut State, cursor: Cursor) {
+        if self.is_group_hidden(&state.view, cursor.group_idx) {
+            // Nothing matches the search query
+            return;
+        }
+        let before = state.checked.clone();
+        let levels_before = state.levels.clone();
+
+        match (cursor.item_idx, self.labels_of(cursor.group_idx)) {
+            (Some(item_idx), Some(labels))
+                if !self.is_item_disabled(cursor) && !self.is_item_locked(cursor) =>
+            {
+                let level = &mut state.levels[cursor.group_idx][item_idx];
+                *level = (*level + 1) % labels.len();
+                state.checked[cursor.group_idx][item_idx] = *level != 0;
+                self.enforce_exclusive(&mut state.checked, cursor.group_idx);
+            }
+            _ => self.toggle(&mut state.checked, cursor),
+        }
+
+        if !self.check_change(state, before) {
+            state.levels = levels_before;
+        }
+    }
+
+    /// Labels of a group whose items cycle through states.
+    fn labels_of(&self, group_idx: usize) -> Option<&[String]> {
+        self.cycle_labels
+            .get(group_idx)
+            .filter(|labels| labels.len() >= 2)
+            .map(|labels| labels.as_slice())
+    }
+
+    /// Runs the change validator and reverts to `before` if it rejects the change.
+    ///
+    /// Returns whether the change was kept and brings the label indices in line with the
+    /// checked matrix.
+    fn check_change(&self, state: &mut State, before: Vec<Vec<bool>>) -> bool {
+        let kept = match self.exceeded_max(&before, &state.checked) {
+            Some(error) => {
+                state.checked = before;
+                state.error = Some(error);
+                false
+            }
+            None => self.validate(state, before),
+        };
+        for (levels, checked) in state.levels.iter_mut().zip(state.checked.iter()) {
+            for (level, &is_checked) in levels.iter_mut().zip(checked.iter()) {
+                if !is_checked {
+                    *level = 0;
+                } else if *level == 0 {
+                    *level = 1;
+                }
+            }
+        }
+        kept
+    }
+
+    /// Runs the change validator, returns `false` if it reverted the change.
+    fn validate(&self, state: &mut State, before: Vec<Vec<bool>>) -> bool {
+        let Some(ref f) = self.validate_change else {
+            return true;
+        };
+        if before == state.checked {
+            return true;
+        }
+        match f(&state.checked) {
+            Ok(status) => {
+                state.status = status;
+                true
+            }
+            Err(status) => {
+                state.checked = before;
+                state.status = Some(status);
+                false
+            }
+        }
+    }
+
+    /// Feeds `key` through the pending key sequence buffer.
+    ///
+    /// Runs a completed sequence and returns `None` when the key was consumed as part of
+    /// one. Otherwise the key is returned for regular handling.
+    fn resolve_sequence(&self, state: &mut State, key: Key) -> Option<Key> {
+        let bindings = &self.keybindings;
+
+        if let Some(since) = state.pending_since {
+            if since.elapsed() >= bindings.sequence_timeout {
+                state.pending_keys.clear();
+            }
+        }
+
+        let had_pending = !state.pending_keys.is_empty();
+        state.pending_keys.push(key.clone());
+
+        for (sequences, to_top) in [(&bindings.top, true), (&bindings.bottom, false)] {
+            if sequences.contains(&state.pending_keys) {
+                state.pending_keys.clear();
+                state.pending_since = None;
+                if self.total_rows(&state.view) == 0 {
+                    // Nothing matches the search query
+                    return None;
+                }
+                state.cursor = if to_top {
+                    self.first_cursor(&state.view)
+                } else {
+                    self.last_cursor(&state.view)
+                };
+                state.page_offset = self.adjust_page_offset(
+                    &state.view,
+                    state.cursor,
+                    state.page_offset,
+                    state.capacity,
+                );
+                return None;
+            }
+        }
+
+        let is_prefix = bindings
+            .top
+            .iter()
+            .chain(bindings.bottom.iter())
+            .any(|seq| {
+                seq.len() > state.pending_keys.len() && seq.starts_with(&state.pending_keys)
+            });
+        if is_prefix {
+            state.pending_since = Some(Instant::now());
+            return None;
+        }
+
+        state.pending_keys.clear();
+        state.pending_since = None;
+
+        if had_pending {
+            // The key broke a pending sequence, give it a chance to start a new one
+            self.resolve_sequence(state, key)
+        } else {
+            Some(key)
+        }
+    }
+
+    fn initial_group_checked(&self) -> Vec<bool> {
+        (0..self.groups.len())
+            .map(|g_idx| self.group_defaults.get(g_idx).copied().unwrap_or(false))
+            .collect()
+    }
+
+    fn initial_checked(&self) -> Vec<Vec<bool>> {
+        self.groups
+            .iter()
+            .enumerate()
+            .map(|(g_idx, group)| {
+                (0..group.items.len())
+                    .map(|i_idx| {
+                        group.states[i_idx] == ItemState::Locked
+                            || self
+                                .defaults
+                                .get(g_idx)
+                                .and_then(|g| g.get(i_idx))
+                                .copied()
+                                .unwrap_or(false)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn apply_states(&mut self, states: Vec<Vec<ItemState>>, checked: &mut [Vec<bool>]) {
+        for ((group, new_states), checked) in self.groups.iter_mut().zip(states).zip(checked) {
+            if new_states.len() != group.items.len() {
+                continue;
+            }
+            for ((old, new), checked) in group.states.iter().zip(&new_states).zip(checked) {
+                if *new == ItemState::Locked {
+                    *checked = true;
+                } else if self.auto_deselect_on_disable
+                    && !matches!(old, ItemState::Disabled { .. })
+                    && matches!(new, ItemState::Disabled { .. })
+                {
+                    *checked = false;
+                }
+            }
+            group.states = new_states;
+        }
+    }
+
+    /// Re-evaluates `visible_when` for every item.
+    fn refresh_hidden(&self, view: &mut View, checked: &[Vec<bool>]) {
+        let Some(ref f) = self.visible_when else {
+            return;
+        };
+        view.hidden = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(g_idx, group)| {
+                group
+                    .items
+                    .iter()
+                    .zip(group.states.iter())
+                    .enumerate()
+                    .map(|(i_idx, (item, state))| !f(g_idx, i_idx, item, state, checked))
+                    .collect()
+            })
+            .collect();
+    }
+
+    fn is_item_hidden(&self, view: &View, group_idx: usize, item_idx: usize) -> bool {
+        if self.hide_disabled
+            && self.is_item_disabled(Cursor {
+                group_idx,
+                item_idx: Some(item_idx),
+            })
+        {
+            return true;
+        }
+        [&view.hidden, &view.filtered].iter().any(|rows| {
+            rows.get(group_idx)
+                .and_then(|g| g.get(item_idx))
+                .copied()
+                .unwrap_or(false)
+        })
+    }
+
+    /// Whether a group is left out entirely because none of its items match the search
+    /// query.
+    fn is_group_hidden(&self, view: &View, group_idx: usize) -> bool {
+        view.filtered_groups
+            .get(group_idx)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Hides the items that don't contain the search query.
+    fn refresh_filter(&self, view: &mut View, query: Option<&str>) {
+        let query = query.unwrap_or_default();
+        if query.is_empty() {
+            view.filtered.clear();
+            view.filtered_groups.clear();
+            return;
+        }
+        (view.filtered, view.filtered_groups) = self
+            .groups
+            .iter()
+            .map(|group| {
+                let label_matches =
+                    self.filter_matches_groups && Self::find_query(&group.label, query).is_some();
+                let filtered: Vec<bool> = group
+                    .items
+                    .iter()
+                    .map(|item| {
+                        !label_matches && Self::find_query(&item.to_string(), query).is_none()
+                    })
+                    .collect();
+                let group_filtered = !label_matches && filtered.iter().all(|&f| f);
+                (filtered, group_filtered)
+            })
+            .unzip();
+    }
+
+    /// Indices of the items of a group that are shown below its header.
+    fn visible_item_indices<'s>(
+        &'s self,
+        view: &'s View,
+        group_idx: usize,
+    ) -> impl Iterator<Item = usize> + 's {
+        let len = if self.is_collapsed(group_idx) {
+            0
+        } else {
+            self.groups[group_idx].items.len()
+        };
+        (0..len).filter(move |&i_idx| !self.is_item_hidden(view, group_idx, i_idx))
+    }
+
+    fn validate_cursor(&self, view: &View, cursor: Cursor) -> Cursor {
+        if self.is_group_hidden(view, cursor.group_idx) {
+            let len = self.groups.len();
+            return match (1..len)
+                .map(|step| (cursor.group_idx + step) % len)
+                .find(|&g_idx| !self.is_group_hidden(view, g_idx))
+            {
+                Some(group_idx) => self.validate_cursor(
+                    view,
+                    Cursor {
+                        group_idx,
+                        item_idx: None,
+                    },
+                ),
+                None => cursor,
+            };
+        }
+        let is_shown = match cursor.item_idx {
+            None => true,
+            Some(item_idx) => self
+                .visible_item_indices(view, cursor.group_idx)
+                .any(|i_idx| i_idx == item_idx),
+        };
+        if self.hide_headers && cursor.item_idx.is_none() {
+            if let Some(item_idx) = self.visible_item_indices(view, cursor.group_idx).next() {
+                return self.validate_cursor(
+                    view,
+                    Cursor {
+                        group_idx: cursor.group_idx,
+                        item_idx: Some(item_idx),
+                    },
+                );
+            }
+        }
+        if !is_shown {
+            return self.validate_cursor(
+                view,
+                Cursor {
+                    group_idx: cursor.group_idx,
+                    item_idx: None,
+                },
+            );
+        }
+        if !self.is_skipped(cursor) {
+            return cursor;
+        }
+        let down = self.move_cursor_down(view, cursor);
+        if !self.is_skipped(down) {
+            return down;
+        }
+        self.move_cursor_up(view, cursor)
+    }
+
+    fn is_collapsed(&self, group_idx: usize) -> bool {
+        !self.hide_headers
+            && self.groups[group_idx]
+                .collapsed
+                .unwrap_or(self.collapsed_by_default)
+    }
+
+    /// Cursor on the header of a group, or on its first item if headers are hidden.
+    fn header_cursor(&self, view: &View, group_idx: usize) -> Cursor {
+        let header = Cursor {
+            group_idx,
+            item_idx: None,
+        };
+        if self.hide_headers {
+            self.validate_cursor(view, header)
+        } else {
+            header
+        }
+    }
+
+    /// Rows taken by the header of a shown group.
+    fn header_rows(&self) -> usize {
+        usize::from(!self.hide_headers)
+    }
+
+    /// Number of item rows shown below the header of a group.
+    fn visible_items(&self, view: &View, group_idx: usize) -> usize {
+        self.visible_item_indices(view, group_idx).count()
+    }
+
+    /// Rows taken by a group, counting its header.
+    fn group_rows(&self, view: &View, group_idx: usize) -> usize {
+        if self.is_group_hidden(view, group_idx) {
+            0
+        } else {
+            self.header_rows() + self.visible_items(view, group_idx)
+        }
+    }
+
+    fn total_rows(&self, view: &View) -> usize {
+        (0..self.groups.len())
+            .map(|g_idx| self.group_rows(view, g_idx))
+            .sum()
+    }
+
+    fn cursor_to_flat(&self, view: &View, cursor: Cursor) -> usize {
+        let mut flat = 0;
+        for g_idx in 0..cursor.group_idx {
+            flat += self.group_rows(view, g_idx);
+        }
+        flat += match cursor.item_idx {
+            None => 0,
+            Some(item_idx) => {
+                self.header_rows()
+                    + self
+                        .visible_item_indices(view, cursor.group_idx)
+                        .take_while(|&i_idx| i_idx < item_idx)
+                        .count()
+            }
+        };
+        flat
+    }
+
+    fn flat_to_cursor(&self, view: &View, flat_idx: usize) -> Cursor {
+        let mut remaining = flat_idx;
+        for g_idx in 0..self.groups.len() {
+            if self.is_group_hidden(view, g_idx) {
+                continue;
+            }
+            if remaining == 0 && !self.hide_headers {
+                return Cursor {
+                    group_idx: g_idx,
+                    item_idx: None,
+                };
+            }
+            remaining -= self.header_rows();
+            let visible_items = self.visible_items(view, g_idx);
+            if remaining < visible_items {
+                return Cursor {
+                    group_idx: g_idx,
+                    item_idx: self.visible_item_indices(view, g_idx).nth(remaining),
+                };
+            }
+            remaining -= visible_items;
+        }
+        Cursor::default()
+    }
+
+    fn is_item_disabled(&self, cursor: Cursor) -> bool {
+        match cursor.item_idx {
+            None => false,
+            Some(item_idx) => {
+                self.is_group_disabled(cursor.group_idx)
+                    || matches!(
+                        self.groups[cursor.group_idx].states.get(item_idx),
+                        Some(ItemState::Disabled { .. })
+                    )
+            }
+        }
+    }
+
+    fn is_item_locked(&self, cursor: Cursor) -> bool {
+        self.item_state(cursor) == Some(&ItemState::Locked)
+    }
+
+    fn is_group_disabled(&self, group_idx: usize) -> bool {
+        self.groups[group_idx].disabled.is_some()
+    }
+
+    /// Whether the cursor moves past the row instead of stopping on it.
+    fn is_skipped(&self, cursor: Cursor) -> bool {
+        self.is_group_disabled(cursor.group_idx)
+            || self.disabled_navigation == DisabledNav::Skip && self.is_item_disabled(cursor)
+    }
+
+    fn first_cursor(&self, view: &View) -> Cursor {
+        let first = self.flat_to_cursor(view, 0);
+        if self.is_skipped(first) {
+            self.move_cursor_down(view, first)
+        } else {
+            first
+        }
+    }
+
+    fn last_cursor(&self, view: &View) -> Cursor {
+        let last = self.flat_to_cursor(view, self.total_rows(view).saturating_sub(1));
+        if self.is_skipped(last) {
+            self.move_cursor_up(view, last)
+        } else {
+            last
+        }
+    }
+
+    fn move_cursor_down(&self, view: &View, cursor: Cursor) -> Cursor {
+        let total = self.total_rows(view);
+        let mut flat = self.cursor_to_flat(view, cursor);
+
+        loop {
+            if flat + 1 >= total {
+                return cursor;
+            }
+            flat += 1;
+            let new_cursor = self.flat_to_cursor(view, flat);
+            if !self.is_skipped(new_cursor) {
+                return new_cursor;
+            }
+        }
+    }
+
+    /// Next enabled item in the given direction with [`item_tab`](Self::item_tab), wrapping
+    /// around at the ends.
+    fn next_item(&self, view: &View, cursor: Cursor, forward: bool) -> Cursor {
+        let total = self.total_rows(view);
+        let flat = self.cursor_to_flat(view, cursor);
+        (1..total)
+            .map(|step| {
+                let flat = if forward {
+                    (flat + step) % total
+                } else {
+                    (flat + total - step) % total
+                };
+                self.flat_to_cursor(view, flat)
+            })
+            .find(|pos| pos.item_idx.is_some() && !self.is_item_disabled(*pos))
+            .unwrap_or(cursor)
+    }
+
+    /// Moves the cursor by one row, wrapping around at the ends with
+    /// [`wrap_around`](Self::wrap_around).
+    fn step_cursor(&self, view: &View, cursor: Cursor, forward: bool) -> Cursor {
+        let next = if forward {
+            self.move_cursor_down(view, cursor)
+        } else {
+            self.move_cursor_up(view, cursor)
+        };
+        let stuck = (next.group_idx, next.item_idx) == (cursor.group_idx, cursor.item_idx);
+        if !stuck || !self.wrap_around || self.total_rows(view) == 0 {
+            return next;
+        }
+        if forward {
+            self.first_cursor(view)
+        } else {
+            self.last_cursor(view)
+        }
+    }
+
+    /// Moves the cursor by up to `rows` rows, stopping at the first or last row.
+    ///
+    /// A skipped row at the target moves the cursor further in the same direction, or back
+    /// if there is no row to stop on beyond it.
+    fn move_cursor_by(&self, view: &View, cursor: Cursor, rows: usize, forward: bool) -> Cursor {
+        let total = self.total_rows(view);
+        if total == 0 {
+            return cursor;
+        }
+        let flat = self.cursor_to_flat(view, cursor);
+        let target = if forward {
+            flat.saturating_add(rows).min(total - 1)
+        } else {
+            flat.saturating_sub(rows)
+        };
+        let target = self.flat_to_cursor(view, target);
+        if !self.is_skipped(target) {
+            return target;
+        }
+        let (ahead, back) = if forward {
+            (
+                self.move_cursor_down(view, target),
+                self.move_cursor_up(view, target),
+            )
+        } else {
+            (
+                self.move_cursor_up(view, target),
+                self.move_cursor_down(view, target),
+            )
+        };
+        if !self.is_skipped(ahead) {
+            ahead
+        } else if !self.is_skipped(back) {
+            back
+        } else {
+            cursor
+        }
+    }
+
+    fn move_cursor_up(&self, view: &View, cursor: Cursor) -> Cursor {
+        let mut flat = self.cursor_to_flat(view, cursor);
+
+        loop {
+            if flat == 0 {
+                return cursor;
+            }
+            flat -= 1;
+            let new_cursor = self.flat_to_cursor(view, flat);
+            if !self.is_skipped(new_cursor) {
+                return new_cursor;
+            }
+        }
+    }
+
+    fn toggle(&self, checked: &mut [Vec<bool>], cursor: Cursor) {
+        match cursor.item_idx {
+            None => {
+                if self.groups[cursor.group_idx].items.is_empty() {
+                    return;
+                }
+                let enabled: Vec<usize> = self.enabled_items(cursor.group_idx).collect();
+                let new_state = !enabled
+                    .iter()
+                    .all(|&item_idx| checked[cursor.group_idx][item_idx]);
+                for item_idx in enabled {
+                    checked[cursor.group_idx][item_idx] = new_state;
+                }
+            }
+            Some(item_idx) => {
+                if !self.is_item_disabled(cursor) && !self.is_item_locked(cursor) {
+                    checked[cursor.group_idx][item_idx] = !checked[cursor.group_idx][item_idx];
+                }
+            }
+        }
+
+        self.enforce_exclusive(checked, cursor.group_idx);
+    }
+
+    /// Indices of the items of a group that can be toggled, i.e. that aren't disabled or
+    /// locked.
+    fn enabled_items(&self, group_idx: usize) -> impl Iterator<Item = usize> + '_ {
+        let group = &self.groups[group_idx];
+        let states = if group.disabled.is_some() {
+            &[]
+        } else {
+            group.states.as_slice()
+        };
+        states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| Self::is_togglable(state))
+            .map(|(item_idx, _)| item_idx)
+    }
+
+    /// Flips every enabled item that isn't hidden, in all groups.
+    fn invert(&self, view: &View, checked: &mut [Vec<bool>]) {
+        for (group_idx, group_checked) in checked.iter_mut().enumerate() {
+            for item_idx in self.enabled_items(group_idx) {
+                if !self.is_item_hidden(view, group_idx, item_idx) {
+                    group_checked[item_idx] = !group_checked[item_idx];
+                }
+            }
+        }
+    }
+
+    /// Clears the groups that are mutually exclusive with `group_idx` if it has a selection.
+    fn enforce_exclusive(&self, checked: &mut [Vec<bool>], group_idx: usize) {
+        if !checked[group_idx].contains(&true) {
+            return;
+        }
+        for set in self
+            .exclusive_groups
+            .iter()
+            .filter(|set| set.contains(&group_idx))
+        {
+            for &other in set.iter().filter(|&&other| other != group_idx) {
+                if other >= self.groups.len() {
+                    continue;
+                }
+                for item_idx in self.enabled_items(other) {
+                    checked[other][item_idx] = false;
+                }
+            }
+        }
+    }
+
+    /// Leaves one group with a selection per exclusive set after a change spanning groups.
+    ///
+    /// `preferred` keeps its selection if it has one, otherwise the first group of the set
+    /// with a selection does.
+    fn enforce_exclusive_sets(&self, checked: &mut [Vec<bool>], preferred: usize) {
+        for set in &self.exclusive_groups {
+            let keep = set
+                .iter()
+                .copied()
+                .filter(|&group_idx| {
+                    group_idx < self.groups.len() && checked[group_idx].contains(&true)
+                })
+                .min_by_key(|&group_idx| group_idx != preferred);
+            if let Some(keep) = keep {
+                self.enforce_exclusive(checked, keep);
+            }
+        }
+    }
+
+    /// Checks or unchecks the item under the cursor, or every enabled item of a group.
+    fn set_checked(&self, checked: &mut [Vec<bool>], cursor: Cursor, value: bool) {
+        for item_idx in self.enabled_items(cursor.group_idx) {
+            if cursor.item_idx.map_or(true, |idx| idx == item_idx) {
+                checked[cursor.group_idx][item_idx] = value;
+            }
+        }
+
+        self.enforce_exclusive(checked, cursor.group_idx);
+    }
+
+    fn is_select_all_selectable(&self, group_idx: usize, item_idx: usize) -> bool {
+        let state = &self.groups[group_idx].states[item_idx];
+        match self.select_all_selectable {
+            _ if self.is_group_disabled(group_idx) || *state == ItemState::Locked => false,
+            Some(ref f) => f(state),
+            None => !matches!(state, ItemState::Disabled { .. }),
+        }
+    }
+
+    fn is_select_all_selected(&self, state: &ItemState, is_checked: bool) -> bool {
+        match self.select_all_selected {
+            Some(ref f) => f(state, is_checked),
+            None => is_checked,
+        }
+    }
+
+    fn select_all(&self, view: &View, checked: &mut [Vec<bool>]) {
+        let targets: Vec<_> = self.select_all_targets(view).collect();
+        self.toggle_all(checked, &targets);
+    }
+
+    /// Checks all targets, or unchecks them if they are all selected already.
+    fn toggle_all(&self, checked: &mut [Vec<bool>], targets: &[(usize, usize)]) {
+        let all_selectable_selected = targets.iter().all(|&(group_idx, item_idx)| {
+            self.is_select_all_selected(
+                &self.groups[group_idx].states[item_idx],
+                checked[group_idx][item_idx],
+            )
+        });
+        for &(group_idx, item_idx) in targets {
+            if all_selectable_selected {
+                checked[group_idx][item_idx] = false;
+            } else if !checked[group_idx][item_idx] && self.below_max(checked, group_idx) {
+                checked[group_idx][item_idx] = true;
+            }
+        }
+    }
+
+    /// Select-all targets on the current page.
+    fn page_targets(&self, state: &State) -> Vec<(usize, usize)> {
+        let layout = self.layout(&state.view);
+        let (visible_end, _) = self.page_end(&layout, state.page_offset, state.capacity);
+        layout
+            .rows(state.page_offset..visible_end)
+            .iter()
+            .filter_map(|pos| Some((pos.group_idx, pos.item_idx?)))
+            .filter(|&(group_idx, item_idx)| self.is_select_all_selectable(group_idx, item_idx))
+            .collect()
+    }
+
+    /// Items select-all acts on: every selectable item that isn't hidden.
+    fn select_all_targets<'s>(
+        &'s self,
+        view: &'s View,
+    ) -> impl Iterator<Item = (usize, usize)> + 's {
+        self.groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group_idx, group)| {
+                (0..group.states.len()).map(move |item_idx| (group_idx, item_idx))
+            })
+            .filter(|&(group_idx, item_idx)| {
+                self.is_select_all_selectable(group_idx, item_idx)
+                    && !self.is_item_hidden(view, group_idx, item_idx)
+            })
+    }
+
+    /// Checkbox state of a group header.
+    ///
+    /// Locked items are always checked and don't take part, so `All` means every other
+    /// item is checked. A group of only locked items falls back to all of its items.
+    fn header_state(&self, group_idx: usize, checked: &[bool]) -> GroupState {
+        let unlocked: Vec<bool> = self.groups[group_idx]
+            .states
+            .iter()
+            .zip(checked.iter())
+            .filter(|(state, _)| **state != ItemState::Locked)
+            .map(|(_, &is_checked)| is_checked)
+            .collect();
+        if unlocked.is_empty() {
+            Self::group_state(checked)
+        } else {
+            Self::group_state(&unlocked)
+        }
+    }
+
+    /// Whether the user can change the checked state of an item.
+    fn is_togglable(state: &ItemState) -> bool {
+        !matches!(state, ItemState::Disabled { .. } | ItemState::Locked)
+    }
+
+    /// Number of checked items and number of items of a group, without disabled items.
+    fn group_counts(&self, group_idx: usize, checked: &[bool]) -> (usize, usize) {
+        let enabled = self.groups[group_idx]
+            .states
+            .iter()
+            .zip(checked)
+            .filter(|(state, _)| !matches!(state, ItemState::Disabled { .. }));
+        enabled.fold((0, 0), |(selected, total), (_, &is_checked)| {
+            (selected + usize::from(is_checked), total + 1)
+        })
+    }
+
+    fn group_state(checked: &[bool]) -> GroupState {
+        let selected_count = checked.iter().filter(|&&b| b).count();
+        let total = checked.len();
+        if total == 0 || selected_count == 0 {
+            GroupState::None
+        } else if selected_count == total {
+            GroupState::All
+        } else {
+            GroupState::Partial
+        }
+    }
+
+    fn adjust_page_offset(
+        &self,
+        view: &View,
+        cursor: Cursor,
+        current_offset: usize,
+        capacity: usize,
+    ) -> usize {
+        if let Some(n) = self.groups_per_page {
+            return self.cursor_to_flat(
+                view,
+                Cursor {
+                    group_idx: cursor.group_idx / n * n,
+                    item_idx: None,
+                },
+            );
+        }
+
+        let flat = self.cursor_to_flat(view, cursor);
+        let layout = self.layout(view);
+        let total = layout.total_rows();
+
+        if capacity >= layout.lines(0..total) {
+            return 0;
+        }
+        let capacity = self.rows_capacity(&layout, capacity);
+
+        let scroll_off = self.scroll_off.min(capacity.saturating_sub(1) / 2);
+        let context_end = (flat + scroll_off + 1).min(total);
+        let mut offset = current_offset.min(flat.saturating_sub(scroll_off));
+        while offset < flat && layout.lines(offset..context_end) > capacity {
+            offset += 1;
+        }
+
+        // Don't leave empty rows at the end after the list shrank
+        let mut max_offset = total;
+        while max_offset > 0 && layout.lines(max_offset - 1..total) <= capacity {
+            max_offset -= 1;
+        }
+        offset.min(max_offset)
+    }
+
+    /// Lists the visible rows and the lines they take.
+    ///
+    /// A header also takes the lines of its prologue and subtitle.
+    fn layout(&self, view: &View) -> Layout {
+        self.layout_with(view, |group_idx| self.is_collapsed(group_idx))
+    }
+
+    /// Like [`layout`](Self::layout), with `is_collapsed` deciding which groups list no items.
+    fn layout_with(&self, view: &View, is_collapsed: impl Fn(usize) -> bool) -> Layout {
+        let mut rows = Vec::new();
+        let mut line_offsets = vec![0];
+        let mut lines = 0;
+        for group_idx in 0..self.groups.len() {
+            if self.is_group_hidden(view, group_idx) {
+                continue;
+            }
+            if !self.hide_headers {
+                let prologue = view.prologue_lines.get(group_idx).map_or(0, Vec::len);
+                lines += 1 + prologue + usize::from(self.groups[group_idx].subtitle.is_some());
+                rows.push(Cursor {
+                    group_idx,
+                    item_idx: None,
+                });
+                line_offsets.push(lines);
+            }
+            let len = if is_collapsed(group_idx) {
+                0
+            } else {
+                self.groups[group_idx].items.len()
+            };
+            for item_idx in (0..len).filter(|&i_idx| !self.is_item_hidden(view, group_idx, i_idx)) {
+                lines += 1;
+                rows.push(Cursor {
+                    group_idx,
+                    item_idx: Some(item_idx),
+                });
+                line_offsets.push(lines);
+            }
+        }
+        Layout { rows, line_offsets }
+    }
+
+    /// Whether the list overflows a page of `capacity` lines and shows scroll indicators.
+    fn has_scroll_indicators(&self, layout: &Layout, capacity: usize) -> bool {
+        self.show_scroll_indicators
+            && self.groups_per_page.is_none()
+            && capacity < layout.lines(0..layout.total_rows())
+    }
+
+    /// Lines of a page left for rows after the [scroll indicators](Self::show_scroll_indicators).
+    fn rows_capacity(&self, layout: &Layout, capacity: usize) -> usize {
+        if self.has_scroll_indicators(layout, capacity) {
+            capacity.saturating_sub(2).max(1)
+        } else {
+            capacity
+        }
+    }
+
+    /// End of the page starting at `page_offset` and the number of lines it takes.
+    fn page_end(&self, layout: &Layout, page_offset: usize, capacity: usize) -> (usize, usize) {
+        let total = layout.total_rows();
+        if let Some(n) = self.groups_per_page {
+            let visible_end = match layout.rows.get(page_offset) {
+                Some(first) => layout
+                    .rows
+                    .iter()
+                    .position(|row| row.group_idx >= first.group_idx + n)
+                    .unwrap_or(total),
+                None => total,
+            };
+            return (visible_end, layout.lines(page_offset..visible_end));
+        }
+
+        let capacity = self.rows_capacity(layout, capacity);
+        let mut visible_end = page_offset;
+        let mut used_lines = 0;
+        while visible_end < total {
+            let height = layout.lines(visible_end..visible_end + 1);
+            if used_lines > 0 && used_lines + height > capacity {
+                break;
+            }
+            used_lines += height;
+            visible_end += 1;
+        }
+        (visible_end, used_lines)
+    }
+
+    /// Label of an item, formatted with [`format_item`](Self::format_item) if set.
+    fn item_text(&self, group_idx: usize, item_idx: usize, checked: bool, active: bool) -> String {
+        let item = &self.groups[group_idx].items[item_idx];
+        match self.format_item {
+            Some(ref f) => f(item, checked, active),
+            None => item.to_string(),
+        }
+    }
+
+    /// Width item labels are padded to with [`align_labels`](Self::align_labels).
+    fn label_width(&self, view: &View, term_width: usize) -> usize {
+        if !self.align_labels {
+            return 0;
+        }
+        let widest = (0..self.groups.len())
+            .flat_map(|group_idx| {
+                self.visible_item_indices(view, group_idx)
+                    .map(move |item_idx| (group_idx, item_idx))
+            })
+            .map(|(group_idx, item_idx)| {
+                measure_text_width(&self.item_text(group_idx, item_idx, false, false))
+            })
+            .max()
+            .unwrap_or(0);
+        widest.min(term_width.saturating_sub(ITEM_PREFIX_WIDTH + 1))
+    }
+
+    /// Keys [`handle_key`](Self::handle_key) gives an action, sequences by their first key.
+    fn bound_keys(&self) -> Vec<&Key> {
+        let bindings = &self.keybindings;
+        let sequences = bindings
+            .top
+            .iter()
+            .chain(&bindings.bottom)
+            .filter_map(|sequence| sequence.first());
+        [
+            &bindings.confirm,
+            &bindings.select_all,
+            &bindings.select_visible,
+            &bindings.invert,
+            &bindings.select_group,
+            &bindings.toggle,
+            &bindings.move_up,
+            &bindings.move_down,
+            &bindings.quit,
+            &bindings.left,
+            &bindings.right,
+            &bindings.hide_disabled,
+            &bindings.reorder_up,
+            &bindings.reorder_down,
+            &bindings.search,
+            &bindings.clear_group,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(sequences)
+        .chain(&self.action_key)
+        .chain(&self.continue_key)
+        .chain([&UNDO_KEY, &REDO_KEY])
+        .collect()
+    }
+
+    /// Hint keys of the items on the current page with [`quick_keys`](Self::quick_keys).
+    fn quick_key_targets(&self, state: &State) -> Vec<(char, Cursor)> {
+        if !self.quick_keys {
+            return Vec::new();
+        }
+        let bound = self.bound_keys();
+        let layout = self.layout(&state.view);
+        let (visible_end, _) = self.page_end(&layout, state.page_offset, state.capacity);
+        let targets = layout
+            .rows(state.page_offset..visible_end)
+            .iter()
+            .copied()
+            .filter(|pos| {
+                pos.item_idx.is_some() && !self.is_item_disabled(*pos) && !self.is_item_locked(*pos)
+            });
+        QUICK_KEYS
+            .chars()
+            .filter(|&c| !bound.contains(&&Key::Char(c)))
+            .zip(targets)
+            .collect()
+    }
+
+    fn render(&self, render: &mut TermThemeRenderer, state: &State) -> Result<()> {
+        if let Some(ref review) = state.review {
+            return self.render_review(render, state, review);
+        }
+
+        let State {
+            ref checked,
+            ref initial,
+            ref levels,
+            cursor,
+            page_offset,
+            capacity,
+            ..
+        } = *state;
+        let layout = self.layout(&state.view);
+        let total = layout.total_rows();
+        let total_lines = layout.lines(0..total);
+        let paging_info = if let Some(n) = self.groups_per_page {
+            let total_pages = (self.groups.len() + n - 1) / n;
+            let current_page = layout.rows.get(page_offset).map_or(0, |row| row.group_idx) / n + 1;
+            Some((current_page, total_pages)).filter(|_| total_pages > 1)
+        } else if capacity > 0 && capacity < total_lines {
+            let rows_capacity = self.rows_capacity(&layout, capacity);
+            let total_pages = (total_lines + rows_capacity - 1) / rows_capacity;
+            let current_page = layout.lines(0..page_offset) / rows_capacity + 1;
+            Some((current_page, total_pages))
+        } else {
+            None
+        };
+        let query = state.query.as_deref().map(|query| {
+            let max_width = state
+                .width
+                .saturating_sub(measure_text_width(&self.prompt) + QUERY_RESERVED_WIDTH);
+            Self::clamp_query(query, max_width)
+        });
+        let count = self
+            .show_count
+            .then(|| (Self::selected_count(&state.checked), self.max_selections));
+        render.group_multi_select_prompt(
+            &self.prompt,
+            count,
+            paging_info,
+            state.recompute.is_some(),
+            query.as_deref(),
+        )?;
+        if self.spacing.after_prompt {
+            render.blank_line()?;
+        }
+        let (visible_end, used_lines) = self.page_end(&layout, page_offset, capacity);
+        let label_width = self.label_width(&state.view, state.width);
+        let quick_keys = self.quick_key_targets(state);
+        let box_width = Self::box_inner_width(state.width);
+
+        if self.boxed {
+            render.group_multi_select_box_top(box_width)?;
+        }
+
+        if page_offset > 0 && self.has_scroll_indicators(&layout, capacity) {
+            render.group_multi_select_scroll_indicator(true)?;
+        }
+
+        for (row, &pos) in layout.rows(page_offset..visible_end).iter().enumerate() {
+            let group = &self.groups[pos.group_idx];
+            if pos.item_idx.is_none() {
+                for line in state
+                    .view
+                    .prologue_lines
+                    .get(pos.group_idx)
+                    .into_iter()
+                    .flatten()
+                {
+                    render.group_multi_select_prologue(line)?;
+                }
+            }
+            let group_state = if self.group_selection {
+                if state.group_checked[pos.group_idx] {
+                    GroupState::All
+                } else {
+                    GroupState::None
+                }
+            } else {
+                self.header_state(pos.group_idx, &checked[pos.group_idx])
+            };
+            let is_active = pos.group_idx == cursor.group_idx && pos.item_idx == cursor.item_idx;
+            let item_text = pos.item_idx.map(|item_idx| {
+                let text = self.item_text(
+                    pos.group_idx,
+                    item_idx,
+                    checked[pos.group_idx][item_idx],
+                    is_active,
+                );
+                let padding = label_width.saturating_sub(measure_text_width(&text));
+                text + &" ".repeat(padding)
+            });
+            let is_checked = pos
+                .item_idx
+                .is_some_and(|item_idx| checked[pos.group_idx][item_idx]);
+
+            let group_disabled = group.disabled.as_ref().map(|reason| ItemState::Disabled {
+                reason: reason.clone(),
+            });
+
+            render.group_multi_select_row(&RowContext {
+                group_idx: pos.group_idx,
+                item_idx: pos.item_idx,
+                text: item_text.as_deref().unwrap_or(&group.label),
+                item_state: pos
+                    .item_idx
+                    .map(|item_idx| group_disabled.as_ref().unwrap_or(&group.states[item_idx])),
+                group_state,
+                checked: is_checked,
+                active: is_active,
+                in_active_group: self.highlight_active_group && pos.group_idx == cursor.group_idx,
+                collapsed: self.is_collapsed(pos.group_idx),
+                counts: self.group_counts(pos.group_idx, &checked[pos.group_idx]),
+                show_counts: self.show_group_counts,
+                constraint_ok: pos.item_idx.is_some()
+                    || self.group_within_limits(pos.group_idx, &checked[pos.group_idx]),
+                recently_changed: state.flash.is_some_and(|(flash, _)| {
+                    flash.group_idx == pos.group_idx && flash.item_idx == pos.item_idx
+                }),
+                changed: pos.item_idx.is_some_and(|item_idx| {
+                    self.mark_changes && is_checked != initial[pos.group_idx][item_idx]
+                }),
+                level: pos.item_idx.and_then(|item_idx| {
+                    self.labels_of(pos.group_idx)
+                        .map(|labels| labels[levels[pos.group_idx][item_idx]].as_str())
+                }),
+                accent: group.accent,
+                label_match: pos
+                    .item_idx
+                    .map_or_else(|| self.label_match(state, pos.group_idx), |_| None),
+                disabled_reason: group.disabled.as_deref(),
+                quick_key: quick_keys.iter().find_map(|&(c, target)| {
+                    (target.group_idx == pos.group_idx && target.item_idx == pos.item_idx)
+                        .then_some(c)
+                }),
+                row,
+            })?;
+
+            if let (None, Some(subtitle)) = (pos.item_idx, &group.subtitle) {
+                render.group_multi_select_subtitle(subtitle)?;
+            }
+        }
+
+        let scroll_indicators = self.has_scroll_indicators(&layout, capacity);
+        if scroll_indicators && visible_end < total {
+            render.group_multi_select_scroll_indicator(false)?;
+        }
+        let indicator_lines = if scroll_indicators {
+            usize::from(page_offset > 0) + usize::from(visible_end < total)
+        } else {
+            0
+        };
+
+        if self.boxed {
+            if self.max_length.is_some() && self.paging {
+                for _ in used_lines + indicator_lines..capacity {
+                    render.group_multi_select_box_padding()?;
+                }
+            }
+            render.group_multi_select_box_bottom(box_width)?;
+        }
+
+        if self.spacing.after_list {
+            render.blank_line()?;
+        }
+
+        if self.focus_tooltip {
+            render.group_multi_select_tooltip(self.tooltip(cursor).unwrap_or_default())?;
+        }
+
+        if self.show_help {
+            match self.help_text {
+                Some(ref text) => render.group_multi_select_help(text)?,
+                None => render.group_multi_select_help(&self.help_line(state.allow_quit))?,
+            }
+        }
+
+        if let Some(ref status) = state.status {
+            render.group_multi_select_status(status)?;
+        }
+
+        if let Some(ref error) = state.error {
+            render.group_multi_select_error(error)?;
+        }
+
+        Ok(())
+    }
+
+    fn render_review(
+        &self,
+        render: &mut TermThemeRenderer,
+        state: &State,
+        review: &Review,
+    ) -> Result<()> {
+        render.group_multi_select_prompt(&self.prompt, None, None, false, None)?;
+        if self.spacing.after_prompt {
+            render.blank_line()?;
+        }
+
+        let offset = (review.cursor + 1).saturating_sub(state.capacity);
+        for (idx, pos) in review
+            .items
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(state.capacity)
+        {
+            let item_idx = pos.item_idx.unwrap_or_default();
+            let group = &self.groups[pos.group_idx];
+            let checked = &state.checked[pos.group_idx];
+            let item_text = self.item_text(
+                pos.group_idx,
+                item_idx,
+                checked[item_idx],
+                idx == review.cursor,
+            );
+            let group_disabled = group.disabled.as_ref().map(|reason| ItemState::Disabled {
+                reason: reason.clone(),
+            });
+            render.group_multi_select_row(&RowContext {
+                group_idx: pos.group_idx,
+                item_idx: pos.item_idx,
+                text: &item_text,
+                item_state: Some(group_disabled.as_ref().unwrap_or(&group.states[item_idx])),
+                // Only headers show the group state
+                group_state: GroupState::None,
+                checked: checked[item_idx],
+                active: idx == review.cursor,
+                in_active_group: false,
+                collapsed: false,
+                counts: (0, 0),
+                show_counts: false,
+                constraint_ok: true,
+                recently_changed: false,
+                changed: false,
+                level: self
+                    .labels_of(pos.group_idx)
+                    .map(|labels| labels[state.levels[pos.group_idx][item_idx]].as_str()),
+                accent: group.accent,
+                label_match: None,
+                quick_key: None,
+                disabled_reason: group.disabled.as_deref(),
+                row: idx - offset,
+            })?;
+        }
+
+        if let Some(ref status) = state.status {
+            render.group_multi_select_status(status)?;
+        }
+
+        if let Some(ref error) = state.error {
+            render.group_multi_select_error(error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Byte range of the first case-insensitive occurrence of `query` in `text`.
+    fn find_query(text: &str, query: &str) -> Option<(usize, usize)> {
+        if query.is_empty() {
+            return None;
+        }
+        text.char_indices().find_map(|(start, _)| {
+            let mut rest = text[start..].char_indices();
+            for q in query.chars().flat_map(char::to_lowercase) {
+                let (_, c) = rest.next()?;
+                if c.to_lowercase().ne(q.to_lowercase()) {
+                    return None;
+                }
+            }
+            let len = rest.next().map_or(text.len() - start, |(idx, _)| idx);
+            Some((start, start + len))
+        })
+    }
+
+    /// Part of the label of a group matched by the search query.
+    fn label_match(&self, state: &State, group_idx: usize) -> Option<(usize, usize)> {
+        if !self.filter_matches_groups {
+            return None;
+        }
+        Self::find_query(&self.groups[group_idx].label, state.query.as_deref()?)
+    }
+
+    /// Describes the key bindings for [`show_help`](Self::show_help).
+    fn help_line(&self, allow_quit: bool) -> String {
+        let bindings = &self.keybindings;
+        let has_disabled = (0..self.groups.len()).any(|group_idx| {
+            self.is_group_disabled(group_idx)
+                || self.groups[group_idx]
+                    .states
+                    .iter()
+                    .any(|state| matches!(state, ItemState::Disabled { .. }))
+        });
+        let entries: [(Vec<&Key>, &str); 7] = [
+            (
+                bindings.move_up.iter().chain(&bindings.move_down).collect(),
+                "move",
+            ),
+            (bindings.toggle.iter().collect(), "toggle"),
+            (bindings.select_all.iter().collect(), "all"),
+            (
+                bindings.search.iter().filter(|_| self.search).collect(),
+                "search",
+            ),
+            (
+                bindings
+                    .hide_disabled
+                    .iter()
+                    .filter(|_| has_disabled)
+                    .collect(),
+                "hide disabled",
+            ),
+            (bindings.confirm.iter().collect(), "accept"),
+            (
+                bindings.quit.iter().filter(|_| allow_quit).collect(),
+                "quit",
+            ),
+        ];
+        entries
+            .iter()
+            .filter(|(keys, _)| !keys.is_empty())
+            .map(|(keys, action)| {
+                let keys: Vec<String> = keys.iter().map(|key| Self::key_name(key)).collect();
+                format!("{} {}", keys.join("/"), action)
+            })
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
+    /// Names of the keys that confirm a warning, for the status line.
+    fn accept_warning_hint(&self) -> String {
+        let keys: Vec<String> = self
+            .keybindings
+            .accept_warning
+            .iter()
+            .map(Self::key_name)
+            .collect();
+        keys.join("/")
+    }
+
+    /// Short name of a key for the help line.
+    fn key_name(key: &Key) -> String {
+        match key {
+            Key::Char(' ') => "space".to_string(),
+            Key::Char(c) => c.to_string(),
+            Key::ArrowUp => "↑".to_string(),
+            Key::ArrowDown => "↓".to_string(),
+            Key::ArrowLeft => "←".to_string(),
+            Key::ArrowRight => "→".to_string(),
+            Key::Enter => "enter".to_string(),
+            Key::Escape => "esc".to_string(),
+            _ => format!("{:?}", key).to_lowercase(),
+        }
+    }
+
+    /// Shortens a search query to its end so it takes at most `max_width` columns.
+    fn clamp_query(query: &str, max_width: usize) -> String {
+        if measure_text_width(query) <= max_width {
+            return query.to_string();
+        }
+        let mut tail: Vec<char> = Vec::new();
+        let mut width = 1;
+        for c in query.chars().rev() {
+            width += measure_text_width(c.encode_utf8(&mut [0; 4]));
+            if width > max_width {
+                break;
+            }
+            tail.push(c);
+        }
+        std::iter::once('…').chain(tail.into_iter().rev()).collect()
+    }
+
+    /// Text describing the item under the cursor.
+    fn tooltip(&self, cursor: Cursor) -> Option<&str> {
+        let item_idx = cursor.item_idx?;
+        let tooltip = self
+            .tooltips
+            .get(cursor.group_idx)
+            .and_then(|g| g.get(item_idx))
+            .and_then(|t| t.as_deref());
+        tooltip.or_else(
+            || match self.groups[cursor.group_idx].states.get(item_idx) {
+                Some(ItemState::Warning { message }) => Some(message.as_str()),
+                Some(ItemState::Disabled { reason }) => Some(reason.as_str()),
+                _ => None,
+            },
+        )
+    }
+
+    fn render_report(
+        &self,
+        render: &mut TermThemeRenderer,
+        checked: &[Vec<bool>],
+        width: usize,
+    ) -> Result<()> {
+        let selected: Vec<String> = checked
+            .iter()
+            .enumerate()
+            .flat_map(|(group_idx, group_checked)| {
+                group_checked
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &is_checked)| is_checked)
+                    .map(move |(item_idx, _)| self.item_text(group_idx, item_idx, true, false))
+            })
+            .collect();
+
+        if self.report_counts {
+            let total = self.groups.iter().map(|group| group.items.len()).sum();
+            let groups = checked
+                .iter()
+                .filter(|group_checked| group_checked.contains(&true))
+                .count();
+            render.group_multi_select_report_counts(selected.len(), total, groups)?;
+        }
+
+        let selected_refs: Vec<&str> = selected.iter().map(|s| s.as_str()).collect();
+        // Leave the last column free to avoid auto-wrapping
+        let width = width.saturating_sub(1);
+        let line_width = |sel: &[&str]| {
+            let mut buf = String::new();
+            let _ =
+                self.theme
+                    .format_group_multi_select_prompt_selection(&mut buf, &self.prompt, sel);
+            measure_text_width(&buf)
+        };
+
+        if selected_refs.is_empty() || line_width(&selected_refs) <= width {
+            return render.group_multi_select_prompt_selection(&self.prompt, &selected_refs);
+        }
+
+        match self.report_overflow {
+            ReportOverflow::Truncate => {
+                for shown in (0..selected_refs.len()).rev() {
+                    let more = format!("+{} more", selected_refs.len() - shown);
+                    let mut sel = selected_refs[..shown].to_vec();
+                    sel.push(&more);
+                    if shown == 0 || line_width(&sel) <= width {
+                        return render.group_multi_select_prompt_selection(&self.prompt, &sel);
+                    }
+                }
+                Ok(())
+            }
+            ReportOverflow::Wrap => {
+                let first = (1..selected_refs.len())
+                    .take_while(|&end| line_width(&selected_refs[..end]) <= width)
+                    .last()
+                    .unwrap_or(1)
+                    .min(selected_refs.len());
+                render
+                    .group_multi_select_prompt_selection(&self.prompt, &selected_refs[..first])?;
+
+                let indent = line_width(&[]);
+                let mut rest = &selected_refs[first..];
+                while !rest.is_empty() {
+                    let mut end = 1;
+                    let mut used = indent + measure_text_width(rest[0]);
+                    while end < rest.len() && used + 2 + measure_text_width(rest[end]) <= width {
+                        used += 2 + measure_text_width(rest[end]);
+                        end += 1;
+                    }
+                    render.group_multi_select_report_continuation(indent, &rest[..end])?;
+                    rest = &rest[end..];
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Outcome reported when the prompt is quit with [`quit_returns_progress`](Self::quit_returns_progress).
+    fn progress(&self, state: &State) -> SelectionOutcome {
+        SelectionOutcome {
+            cancelled: true,
+            ..self.build_outcome(state)
+        }
+    }
+
+    fn build_outcome(&self, state: &State) -> SelectionOutcome {
+        SelectionOutcome {
+            selection: self
+                .build_result(&state.checked)
+                .into_iter()
+                .zip(state.order.iter())
+                .map(|(positions, order)| positions.into_iter().map(|pos| order[pos]).collect())
+                .collect(),
+            was_modified: state.checked != state.initial
+                || state.group_checked != state.initial_group_checked,
+            order: state.order.clone(),
+            levels: state
+                .levels
+                .iter()
+                .zip(state.order.iter())
+                .map(|(levels, order)| {
+                    let mut original = vec![0; levels.len()];
+                    for (pos, &level) in levels.iter().enumerate() {
+                        original[order[pos]] = level;
                     }
+                    original
+                })
+                .collect(),
+            groups: state.group_checked.clone(),
+            cancelled: false,
+        }
+    }
+
+    /// Selected item texts per group, in their final order.
+    fn labeled(&self, outcome: &SelectionOutcome) -> Vec<(String, Vec<String>)> {
+        self.groups
+            .iter()
+            .zip(outcome.selection.iter().zip(outcome.order.iter()))
+            .map(|(group, (indices, order))| {
+                let items = order
+                    .iter()
+                    .zip(group.items.iter())
+                    .filter(|(idx, _)| indices.contains(idx))
+                    .map(|(_, item)| item.to_string())
+                    .collect();
+                (group.label.clone(), items)
+            })
+            .collect()
+    }
+
+    /// Clones of the selected items per group, in their final order.
+    fn values(&self, outcome: &SelectionOutcome) -> Vec<Vec<T>>
+    where
+        T: Clone,
+    {
+        self.groups
+            .iter()
+            .zip(outcome.selection.iter().zip(outcome.order.iter()))
+            .map(|(group, (indices, order))| {
+                order
+                    .iter()
+                    .zip(group.items.iter())
+                    .filter(|(idx, _)| indices.contains(idx))
+                    .map(|(_, item)| item.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn detailed(&self, outcome: &SelectionOutcome) -> Vec<GroupSelection<T>>
+    where
+        T: Clone,
+    {
+        self.groups
+            .iter()
+            .zip(outcome.selection.iter().zip(outcome.order.iter()))
+            .map(|(group, (indices, order))| GroupSelection {
+                label: group.label.clone(),
+                selected: order
+                    .iter()
+                    .zip(group.items.iter().zip(group.states.iter()))
+                    .filter(|(idx, _)| indices.contains(idx))
+                    .map(|(&index, (item, state))| SelectedItem {
+                        index,
+                        value: item.clone(),
+                        had_warning: matches!(state, ItemState::Warning { .. }),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Original indices of the enabled items that aren't selected, per group.
+    fn unselected(&self, outcome: &SelectionOutcome) -> Vec<Vec<usize>> {
+        self.groups
+            .iter()
+            .zip(outcome.selection.iter().zip(outcome.order.iter()))
+            .map(|(group, (indices, order))| {
+                let mut unselected: Vec<usize> = order
+                    .iter()
+                    .zip(group.states.iter())
+                    .filter(|(idx, state)| {
+                        !indices.contains(idx)
+                            && group.disabled.is_none()
+                            && !matches!(state, ItemState::Disabled { .. })
+                    })
+                    .map(|(&idx, _)| idx)
+                    .collect();
+                unselected.sort_unstable();
+                unselected
+            })
+            .collect()
+    }
+
+    /// Formats a selection as described in
+    /// [`emit_machine_readable`](Self::emit_machine_readable).
+    fn selection_path(selection: &[Vec<usize>]) -> String {
+        selection
+            .iter()
+            .enumerate()
+            .filter(|(_, indices)| !indices.is_empty())
+            .map(|(g_idx, indices)| {
+                let items: Vec<String> = indices.iter().map(|idx| idx.to_string()).collect();
+                format!("{}:{}", g_idx, items.join(","))
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    fn build_result(&self, checked: &[Vec<bool>]) -> Vec<Vec<usize>> {
+        checked
+            .iter()
+            .map(|group_checked| {
+                group_checked
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &is_checked)| is_checked)
+                    .map(|(idx, _)| idx)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Maps the indices returned by [`GroupMultiSelect::interact`] to the items they refer to.
+///
+/// `items` holds the items of every group in the order they were added. Indices without
+/// a matching item, e.g. from a selection made against an older list, are skipped.
+pub fn map_selection<'b, U, I>(selection: &[Vec<usize>], items: &'b [I]) -> Vec<Vec<&'b U>>
+where
+    I: AsRef<[U]>,
+{
+    selection
+        .iter()
+        .enumerate()
+        .map(|(group_idx, indices)| {
+            let group = items.get(group_idx).map_or(&[][..], |group| group.as_ref());
+            indices.iter().filter_map(|&idx| group.get(idx)).collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_TERM_SIZE: (u16, u16) = (24, 80);
+
+    #[test]
+    fn test_cursor_conversion_roundtrip() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"]);
+
+        for flat in 0..gs.total_rows(&View::default()) {
+            let cursor = gs.flat_to_cursor(&View::default(), flat);
+            assert_eq!(gs.cursor_to_flat(&View::default(), cursor), flat);
+        }
+    }
+
+    #[test]
+    fn test_group_state() {
+        assert!(matches!(
+            GroupMultiSelect::<&str>::group_state(&[]),
+            GroupState::None
+        ));
+        assert!(matches!(
+            GroupMultiSelect::<&str>::group_state(&[false, false]),
+            GroupState::None
+        ));
+        assert!(matches!(
+            GroupMultiSelect::<&str>::group_state(&[true, false]),
+            GroupState::Partial
+        ));
+        assert!(matches!(
+            GroupMultiSelect::<&str>::group_state(&[true, true]),
+            GroupState::All
+        ));
+    }
+
+    #[test]
+    fn test_toggle_group() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"]);
+
+        let mut checked = vec![vec![false, false], vec![false]];
+        let cursor = Cursor {
+            group_idx: 0,
+            item_idx: None,
+        };
+
+        gs.toggle(&mut checked, cursor);
+        assert_eq!(checked[0], vec![true, true]);
+
+        gs.toggle(&mut checked, cursor);
+        assert_eq!(checked[0], vec![false, false]);
+    }
+
+    #[test]
+    fn test_toggle_item() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group("A", vec!["a1", "a2"]);
+
+        let mut checked = vec![vec![false, false]];
+        let cursor = Cursor {
+            group_idx: 0,
+            item_idx: Some(1),
+        };
+
+        gs.toggle(&mut checked, cursor);
+        assert_eq!(checked[0], vec![false, true]);
+    }
+
+    #[test]
+    fn test_disabled_item_cannot_toggle() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group_with_states(
+            "A",
+            vec![
+                ("a1", ItemState::Normal),
+                (
+                    "a2",
+                    ItemState::Disabled {
+                        reason: "test".into(),
+                    },
+                ),
+            ],
+        );
+
+        let mut checked = vec![vec![false, false]];
+        let cursor = Cursor {
+            group_idx: 0,
+            item_idx: Some(1),
+        };
+
+        gs.toggle(&mut checked, cursor);
+        assert_eq!(checked[0], vec![false, false]);
+    }
+
+    #[test]
+    fn test_group_toggle_skips_disabled() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group_with_states(
+            "A",
+            vec![
+                ("a1", ItemState::Normal),
+                (
+                    "a2",
+                    ItemState::Disabled {
+                        reason: "test".into(),
+                    },
+                ),
+                ("a3", ItemState::Normal),
+            ],
+        );
+
+        let mut checked = vec![vec![false, false, false]];
+        let cursor = Cursor {
+            group_idx: 0,
+            item_idx: None,
+        };
+
+        gs.toggle(&mut checked, cursor);
+        assert_eq!(checked[0], vec![true, false, true]);
+    }
+
+    #[test]
+    fn test_cursor_skips_disabled() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group_with_states(
+            "A",
+            vec![
+                ("a1", ItemState::Normal),
+                (
+                    "a2",
+                    ItemState::Disabled {
+                        reason: "test".into(),
+                    },
+                ),
+                ("a3", ItemState::Normal),
+            ],
+        );
+
+        let cursor = Cursor {
+            group_idx: 0,
+            item_idx: Some(0),
+        };
+        let new_cursor = gs.move_cursor_down(&View::default(), cursor);
+        assert_eq!(new_cursor.item_idx, Some(2));
+
+        let back_cursor = gs.move_cursor_up(&View::default(), new_cursor);
+        assert_eq!(back_cursor.item_idx, Some(0));
+    }
+
+    #[test]
+    fn test_select_all_default() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    (
+                        "a2",
+                        ItemState::Disabled {
+                            reason: "test".into(),
+                        },
+                    ),
+                ],
+            )
+            .group("B", vec!["b1"]);
+
+        let mut checked = vec![vec![false, false], vec![true]];
+        gs.select_all(&View::default(), &mut checked);
+        assert_eq!(checked, vec![vec![true, false], vec![true]]);
+
+        gs.select_all(&View::default(), &mut checked);
+        assert_eq!(checked, vec![vec![false, false], vec![false]]);
+    }
+
+    #[test]
+    fn test_select_all_custom_predicates() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    (
+                        "a2",
+                        ItemState::Warning {
+                            message: "test".into(),
+                        },
+                    ),
+                ],
+            )
+            .select_all_selectable(|state| matches!(state, ItemState::Normal));
+
+        let mut checked = vec![vec![false, false]];
+        gs.select_all(&View::default(), &mut checked);
+        assert_eq!(checked, vec![vec![true, false]]);
+
+        let gs = gs.select_all_selected(|_, _| false);
+        gs.select_all(&View::default(), &mut checked);
+        assert_eq!(checked, vec![vec![true, false]]);
+    }
+
+    #[test]
+    fn test_apply_states_revalidates_cursor() {
+        let mut gs: GroupMultiSelect<'_, &str> =
+            GroupMultiSelect::new().group("A", vec!["a1", "a2", "a3"]);
+
+        let mut checked = vec![vec![false, true, false]];
+        gs.apply_states(
+            vec![vec![
+                ItemState::Normal,
+                ItemState::Disabled {
+                    reason: "test".into(),
+                },
+                ItemState::Normal,
+            ]],
+            &mut checked,
+        );
+        assert_eq!(checked, vec![vec![false; 3]]);
+
+        let cursor = gs.validate_cursor(
+            &View::default(),
+            Cursor {
+                group_idx: 0,
+                item_idx: Some(1),
+            },
+        );
+        assert_eq!(cursor.item_idx, Some(2));
+
+        // Mismatched lengths keep the current states
+        gs.apply_states(vec![vec![ItemState::Normal]], &mut checked);
+        assert!(gs.is_item_disabled(Cursor {
+            group_idx: 0,
+            item_idx: Some(1),
+        }));
+    }
+
+    #[test]
+    fn test_outcome_was_modified() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .defaults(vec![vec![true]]);
+
+        let initial = gs.initial_checked();
+        assert_eq!(initial, vec![vec![true, false]]);
+
+        let mut state = gs.new_state(initial, TEST_TERM_SIZE);
+        let outcome = gs.build_outcome(&state);
+        assert_eq!(outcome.selection, vec![vec![0]]);
+        assert!(!outcome.was_modified);
+
+        gs.toggle(
+            &mut state.checked,
+            Cursor {
+                group_idx: 0,
+                item_idx: Some(1),
+            },
+        );
+        assert!(gs.build_outcome(&state).was_modified);
+
+        gs.toggle(
+            &mut state.checked,
+            Cursor {
+                group_idx: 0,
+                item_idx: Some(1),
+            },
+        );
+        assert!(!gs.build_outcome(&state).was_modified);
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::Char('G'), false);
+        assert_eq!(gs.cursor_to_flat(&state.view, state.cursor), 4);
+
+        // A lone `g` waits for the rest of the sequence
+        gs.handle_key(&mut state, Key::Char('g'), false);
+        assert_eq!(gs.cursor_to_flat(&state.view, state.cursor), 4);
+        gs.handle_key(&mut state, Key::Char('g'), false);
+        assert_eq!(gs.cursor_to_flat(&state.view, state.cursor), 0);
+
+        // An unrelated key breaks the sequence and is handled normally
+        gs.handle_key(&mut state, Key::Char('g'), false);
+        gs.handle_key(&mut state, Key::Char('j'), false);
+        assert_eq!(gs.cursor_to_flat(&state.view, state.cursor), 1);
+        gs.handle_key(&mut state, Key::Char('g'), false);
+        assert_eq!(gs.cursor_to_flat(&state.view, state.cursor), 1);
+    }
+
+    #[test]
+    fn test_key_sequence_timeout() {
+        let keybindings = KeyBindings {
+            sequence_timeout: Duration::ZERO,
+            ..KeyBindings::default()
+        };
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .with_keybindings(keybindings);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        state.cursor = gs.last_cursor(&state.view);
+
+        gs.handle_key(&mut state, Key::Char('g'), false);
+        gs.handle_key(&mut state, Key::Char('g'), false);
+        assert_eq!(gs.cursor_to_flat(&state.view, state.cursor), 2);
+        assert_eq!(state.pending_keys, vec![Key::Char('g')]);
+    }
+
+    #[test]
+    fn test_boxed_reserves_border_rows() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group("A", vec!["a1"]);
+        assert_eq!(gs.capacity((10, 80)), 9);
+
+        let gs = gs.boxed(true);
+        assert_eq!(gs.capacity((10, 80)), 7);
+        assert_eq!(gs.max_length(5).capacity((10, 80)), 5);
+    }
+
+    #[test]
+    fn test_collapsed_groups() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_collapsed("A", vec!["a1", "a2"], true)
+            .group("B", vec!["b1"])
+            .collapsed_by_default(true)
+            .group_collapsed("C", vec!["c1"], false);
+
+        assert_eq!(gs.total_rows(&View::default()), 4);
+        for flat in 0..gs.total_rows(&View::default()) {
+            let cursor = gs.flat_to_cursor(&View::default(), flat);
+            assert_eq!(gs.cursor_to_flat(&View::default(), cursor), flat);
+        }
+
+        let cursor = gs.move_cursor_down(&View::default(), Cursor::default());
+        assert_eq!((cursor.group_idx, cursor.item_idx), (1, None));
+        let cursor = gs.move_cursor_down(
+            &View::default(),
+            gs.move_cursor_down(&View::default(), cursor),
+        );
+        assert_eq!((cursor.group_idx, cursor.item_idx), (2, Some(0)));
+    }
+
+    #[test]
+    fn test_collapse_and_expand_keys() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::ArrowLeft, false);
+        assert_eq!((state.cursor.group_idx, state.cursor.item_idx), (0, None));
+        assert_eq!(gs.total_rows(&state.view), 3);
+
+        gs.handle_key(&mut state, Key::ArrowRight, false);
+        assert_eq!(gs.total_rows(&state.view), 5);
+    }
+
+    #[test]
+    fn test_validate_change_sets_status() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .validate_change(|checked| {
+                let count = checked.iter().flatten().filter(|&&c| c).count();
+                if count > 1 {
+                    Err("can't exceed 1 selection".to_string())
+                } else {
+                    Ok(Some(format!("{} selected", count)))
                 }
-                Key::Enter => {
-                    if self.clear {
-                        render.clear()?;
-                    }
+            });
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        assert_eq!(state.capacity, 22);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.status.as_deref(), Some("1 selected"));
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![true, false]]);
+        assert_eq!(state.status.as_deref(), Some("can't exceed 1 selection"));
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(state.status, None);
+    }
+
+    #[test]
+    fn test_group_from_iter() {
+        let gs: GroupMultiSelect<'_, String> = GroupMultiSelect::new()
+            .group_from_iter("A", (1..=2).map(|i| format!("a{}", i)))
+            .group_with_states_from_iter(
+                "B",
+                ["b1", "b2"]
+                    .iter()
+                    .map(|s| (s.to_string(), ItemState::Normal)),
+            );
+
+        assert_eq!(gs.groups[0].items, vec!["a1", "a2"]);
+        assert_eq!(gs.groups[1].states.len(), 2);
+        assert_eq!(gs.total_rows(&View::default()), 6);
+    }
+
+    #[test]
+    fn test_reveal_item() {
+        let items: Vec<String> = (0..30).map(|i| i.to_string()).collect();
+        let mut gs: GroupMultiSelect<'_, String> = GroupMultiSelect::new()
+            .collapsed_by_default(true)
+            .group("A", vec!["a1".to_string()])
+            .group_with_states(
+                "B",
+                items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let state = if i == 29 {
+                            ItemState::Disabled {
+                                reason: "gone".to_string(),
+                            }
+                        } else {
+                            ItemState::Normal
+                        };
+                        (item, state)
+                    })
+                    .collect(),
+            );
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.reveal_item(&mut state, 5, Some(100));
+        assert!(!gs.is_collapsed(1));
+        assert!(gs.is_collapsed(0));
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(28))
+        );
+
+        let flat = gs.cursor_to_flat(&state.view, state.cursor);
+        assert!(flat >= state.page_offset && flat < state.page_offset + state.capacity);
+    }
+
+    #[test]
+    fn test_horizontal_keys_check() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    (
+                        "a2",
+                        ItemState::Disabled {
+                            reason: "no".to_string(),
+                        },
+                    ),
+                    ("a3", ItemState::Normal),
+                ],
+            )
+            .with_keybindings(KeyBindings {
+                horizontal: HorizontalKeys::Check,
+                ..KeyBindings::default()
+            });
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::ArrowRight, false);
+        gs.handle_key(&mut state, Key::ArrowRight, false);
+        assert_eq!(state.checked, vec![vec![true, false, false]]);
+
+        gs.handle_key(&mut state, Key::ArrowUp, false);
+        gs.handle_key(&mut state, Key::Char('l'), false);
+        assert_eq!(state.checked, vec![vec![true, false, true]]);
+        assert!(!gs.is_collapsed(0));
+
+        gs.handle_key(&mut state, Key::ArrowLeft, false);
+        assert_eq!(state.checked, vec![vec![false, false, false]]);
+    }
+
+    #[test]
+    fn test_group_with_states_checks_length() {
+        assert!(Group::with_states("A", vec!["a1", "a2"], vec![ItemState::Normal]).is_err());
+
+        let group = Group::with_states("A", vec!["a1"], vec![ItemState::Normal]).unwrap();
+        assert_eq!(group.states.len(), 1);
+    }
+
+    #[test]
+    fn test_visible_when() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1", "b2", "b3"])
+            .visible_when(|g_idx, i_idx, _, _, checked| g_idx == 0 || !checked[0][0] || i_idx != 1);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        gs.refresh_hidden(&mut state.view, &state.checked);
+        assert_eq!(gs.total_rows(&state.view), 7);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(gs.total_rows(&state.view), 6);
+        for flat in 0..gs.total_rows(&state.view) {
+            let cursor = gs.flat_to_cursor(&state.view, flat);
+            assert_eq!(gs.cursor_to_flat(&state.view, cursor), flat);
+        }
+
+        for _ in 0..4 {
+            gs.handle_key(&mut state, Key::ArrowDown, false);
+        }
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(2))
+        );
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked[1], vec![true, false, true]);
+    }
+
+    #[test]
+    fn test_labeled() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"]);
+
+        let outcome = SelectionOutcome {
+            selection: vec![vec![1], vec![]],
+            was_modified: true,
+            order: vec![vec![0, 1], vec![0]],
+            levels: vec![vec![0, 1], vec![0]],
+            groups: vec![false, false],
+            cancelled: false,
+        };
+        assert_eq!(
+            gs.labeled(&outcome),
+            vec![
+                ("A".to_string(), vec!["a2".to_string()]),
+                ("B".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paging_disabled() {
+        let items: Vec<usize> = (0..50).collect();
+        let mut gs: GroupMultiSelect<'_, usize> =
+            GroupMultiSelect::new().group("A", items).paging(false);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        for _ in 0..40 {
+            gs.handle_key(&mut state, Key::ArrowDown, false);
+        }
+        assert_eq!(state.cursor.item_idx, Some(39));
+        assert_eq!(state.page_offset, 0);
+    }
+
+    #[test]
+    fn test_focus_tooltip() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    (
+                        "a2",
+                        ItemState::Warning {
+                            message: "careful".to_string(),
+                        },
+                    ),
+                ],
+            )
+            .tooltips(vec![vec![Some("first".to_string())]])
+            .focus_tooltip(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        assert_eq!(state.capacity, 22);
+        assert_eq!(gs.tooltip(state.cursor), None);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(gs.tooltip(state.cursor), Some("first"));
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(gs.tooltip(state.cursor), Some("careful"));
+    }
+
+    #[test]
+    fn test_group_selection() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .group_selection(true)
+            .group_defaults(vec![false, true]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![false, false], vec![false]]);
+
+        let outcome = gs.build_outcome(&state);
+        assert_eq!(outcome.groups, vec![true, true]);
+        assert!(outcome.was_modified);
+    }
+
+    #[test]
+    fn test_reorder_items() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2", "a3"])
+            .defaults(vec![vec![true, false, false]])
+            .reorderable(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char('J'), false);
+        gs.handle_key(&mut state, Key::Char('J'), false);
+        gs.handle_key(&mut state, Key::Char('J'), false);
+        assert_eq!(state.cursor.item_idx, Some(2));
+        assert_eq!(gs.groups[0].items, vec!["a2", "a3", "a1"]);
+
+        gs.handle_key(&mut state, Key::Char('K'), false);
+        let outcome = gs.build_outcome(&state);
+        assert_eq!(outcome.order, vec![vec![1, 0, 2]]);
+        assert_eq!(outcome.selection, vec![vec![0]]);
+        assert!(!outcome.was_modified);
+    }
+
+    #[test]
+    fn test_selection_path() {
+        assert_eq!(
+            GroupMultiSelect::<&str>::selection_path(&[vec![0, 1], vec![], vec![3]]),
+            "0:0,1;2:3"
+        );
+        assert_eq!(GroupMultiSelect::<&str>::selection_path(&[vec![]]), "");
+    }
+
+    #[test]
+    fn test_zero_height_terminal() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .boxed(true);
+        let mut state = gs.new_state(gs.initial_checked(), (0, 80));
+        assert_eq!(state.capacity, 1);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(state.page_offset, 2);
+    }
+
+    #[test]
+    fn test_review_step() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .defaults(vec![vec![true, false], vec![true]])
+            .review_step(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Continue
+        ));
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![false]]);
+
+        gs.handle_key(&mut state, Key::Escape, false);
+        assert!(state.review.is_none());
+        gs.handle_key(&mut state, Key::Enter, false);
+        assert_eq!(state.review.as_ref().map(|r| r.items.len()), Some(1));
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Accept
+        ));
+    }
+
+    #[test]
+    fn test_component_frames() {
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .component((3, 80))
+            .unwrap();
+
+        let frame = component.frame().unwrap();
+        assert_eq!(frame.lines.len(), 3);
+        assert!(frame.lines[0].contains("Pick"));
+        assert!(frame.lines[1].contains('A'));
+
+        assert_eq!(
+            component.handle_key(Key::ArrowDown),
+            ComponentEvent::Pending
+        );
+        assert_eq!(
+            component.handle_key(Key::ArrowDown),
+            ComponentEvent::Pending
+        );
+        assert_eq!(
+            component.handle_key(Key::Char(' ')),
+            ComponentEvent::Pending
+        );
+        assert!(component.frame().unwrap().lines[2].contains("a2"));
+
+        match component.handle_key(Key::Enter) {
+            ComponentEvent::Accepted { selection, .. } => {
+                assert_eq!(selection.selection, vec![vec![1]])
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_exclusive_groups() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .group("C", vec!["c1"])
+            .exclusive_groups(&[0, 1]);
+        let mut checked = vec![vec![true, false], vec![false], vec![true]];
+
+        gs.toggle(
+            &mut checked,
+            Cursor {
+                group_idx: 1,
+                item_idx: Some(0),
+            },
+        );
+        assert_eq!(checked, vec![vec![false, false], vec![true], vec![true]]);
+
+        gs.toggle(
+            &mut checked,
+            Cursor {
+                group_idx: 0,
+                item_idx: None,
+            },
+        );
+        assert_eq!(checked, vec![vec![true, true], vec![false], vec![true]]);
+    }
+
+    #[test]
+    fn test_exclusive_groups_bulk_keys() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .group("B", vec!["b1"])
+                .group("C", vec!["c1"])
+                .exclusive_groups(&[0, 1])
+        };
+
+        // Invert keeps the group under the cursor
+        let keys = [
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::Char('i'),
+        ];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![], vec![0], vec![0]]));
+
+        // or the first group of the set with a selection
+        let keys = [Key::End, Key::Char('i')];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![0, 1], vec![], vec![0]]));
+
+        let keys = [Key::ArrowDown, Key::Char('A')];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![0, 1], vec![], vec![0]]));
+
+        let keys = [Key::End, Key::Char('a')];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![0, 1], vec![], vec![0]]));
+
+        let keys = [
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::Char('a'),
+        ];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![], vec![0], vec![0]]));
+
+        let keys = [Key::ArrowDown, Key::Char(' '), Key::End, Key::Char('S')];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![0], vec![], vec![0]]));
+
+        // Select-group clears the other groups of the set like toggling does
+        let keys = [
+            Key::ArrowDown,
+            Key::Char(' '),
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::Char('S'),
+        ];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![], vec![0], vec![]]));
+    }
+
+    #[test]
+    fn test_hide_disabled() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    (
+                        "a1",
+                        ItemState::Disabled {
+                            reason: "no".to_string(),
+                        },
+                    ),
+                    ("a2", ItemState::Normal),
+                ],
+            )
+            .hide_disabled_by_default(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        assert_eq!(gs.total_rows(&state.view), 2);
+        assert_eq!(gs.flat_to_cursor(&state.view, 1).item_idx, Some(1));
+
+        gs.handle_key(&mut state, Key::Char('d'), false);
+        assert_eq!(gs.total_rows(&state.view), 3);
+    }
+
+    #[test]
+    fn test_hide_disabled_binding_yields_to_user_bindings() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group_with_states(
+                    "A",
+                    vec![
+                        ("a1", ItemState::Normal),
+                        (
+                            "a2",
+                            ItemState::Disabled {
+                                reason: "no".to_string(),
+                            },
+                        ),
+                    ],
+                )
+                .show_help(true)
+        };
+        let keys = vec![Key::ArrowDown, Key::Char('d'), Key::Enter];
+
+        let toggle_d = gs().with_keybindings(KeyBindings {
+            toggle: vec![Key::Char('d')],
+            ..KeyBindings::default()
+        });
+        assert_eq!(toggle_d.run_with_events(keys).unwrap(), Some(vec![vec![0]]));
+
+        let mut component = gs().component(TEST_TERM_SIZE).unwrap();
+        assert!(component
+            .frame()
+            .unwrap()
+            .lines
+            .last()
+            .unwrap()
+            .contains("d hide disabled"));
+        component.handle_key(Key::Char('d'));
+        assert_eq!(component.prompt.total_rows(&component.state.view), 2);
+    }
+
+    #[test]
+    fn test_highlight_active_group() {
+        let mut component = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .group("B", vec!["b1"])
+            .highlight_active_group(true)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        component.handle_key(Key::ArrowDown);
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines[1], "· ○ A (0/1)");
+        assert_eq!(lines[3], "  ○ B (0/1)");
+    }
+
+    #[test]
+    fn test_run_with_events() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .group("B", vec!["b1"])
+        };
+
+        let keys = vec![
+            Key::ArrowDown,
+            Key::Char(' '),
+            Key::Char('G'),
+            Key::Char(' '),
+        ];
+        let mut accept = keys.clone();
+        accept.push(Key::Enter);
+        assert_eq!(
+            gs().run_with_events(accept).unwrap(),
+            Some(vec![vec![0], vec![0]])
+        );
+
+        let mut quit = keys.clone();
+        quit.push(Key::Escape);
+        assert_eq!(gs().run_with_events(quit).unwrap(), None);
+
+        assert!(gs().run_with_events(keys).is_err());
+    }
+
+    #[test]
+    fn test_header_state_ignores_locked_items() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    ("a2", ItemState::Locked),
+                    ("a3", ItemState::Normal),
+                ],
+            )
+            .group_with_states(
+                "B",
+                vec![
+                    ("b1", ItemState::Normal),
+                    (
+                        "b2",
+                        ItemState::Disabled {
+                            reason: "test".into(),
+                        },
+                    ),
+                ],
+            );
+
+        assert_eq!(gs.header_state(0, &[false, true, false]), GroupState::None);
+        assert_eq!(
+            gs.header_state(0, &[true, true, false]),
+            GroupState::Partial
+        );
+        assert_eq!(gs.header_state(0, &[true, true, true]), GroupState::All);
+
+        // Disabled items count like any other item
+        assert_eq!(gs.header_state(1, &[true, false]), GroupState::Partial);
+        assert_eq!(gs.header_state(1, &[false, true]), GroupState::Partial);
+        assert_eq!(gs.header_state(1, &[true, true]), GroupState::All);
+    }
+
+    #[test]
+    fn test_focus_group() {
+        let items: Vec<usize> = (0..40).collect();
+        let mut gs: GroupMultiSelect<'_, usize> = GroupMultiSelect::new()
+            .group("A", items)
+            .group("B", vec![0])
+            .focus_on_violation(true)
+            .show_scroll_indicators(false);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.focus_group(&mut state, 1);
+        assert_eq!((state.cursor.group_idx, state.cursor.item_idx), (1, None));
+        assert_eq!(state.page_offset, 41 - state.capacity + 1);
+    }
+
+    #[test]
+    fn test_max_height_fraction() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .max_height_fraction(0.5);
+        assert_eq!(gs.capacity(TEST_TERM_SIZE), 12);
+        assert_eq!(gs.capacity((1, 80)), 1);
+        assert_eq!(gs.max_length(5).capacity(TEST_TERM_SIZE), 5);
+    }
+
+    #[test]
+    fn test_auto_expand_on_focus() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1", "b2"])
+            .auto_expand_on_focus(true);
+        let mut state = gs.start(TEST_TERM_SIZE).unwrap();
+        assert_eq!(gs.total_rows(&state.view), 4);
+
+        for _ in 0..3 {
+            gs.handle_key(&mut state, Key::ArrowDown, false);
+        }
+        assert_eq!((state.cursor.group_idx, state.cursor.item_idx), (1, None));
+        assert!(gs.is_collapsed(0));
+        assert!(!gs.is_collapsed(1));
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(0))
+        );
+
+        gs.handle_key(&mut state, Key::ArrowUp, false);
+        gs.handle_key(&mut state, Key::ArrowUp, false);
+        assert_eq!((state.cursor.group_idx, state.cursor.item_idx), (0, None));
+        assert!(gs.is_collapsed(1));
+        assert_eq!(gs.total_rows(&state.view), 4);
+    }
+
+    #[test]
+    fn test_cycle_labels() {
+        let labels = vec!["off".to_string(), "on".to_string(), "auto".to_string()];
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("Flags", vec!["f1", "f2"])
+            .cycle_labels(0, labels);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.levels, vec![vec![2, 0]]);
+        assert_eq!(state.checked, vec![vec![true, false]]);
+
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![false, false]]);
+
+        gs.handle_key(&mut state, Key::ArrowUp, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(gs.build_outcome(&state).levels, vec![vec![1, 1]]);
+    }
+
+    #[test]
+    fn test_row_context_theme() {
+        struct RowTheme;
+
+        impl Theme for RowTheme {
+            fn format_group_multi_select_row(
+                &self,
+                f: &mut dyn std::fmt::Write,
+                row: &RowContext<'_>,
+            ) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{}:{}:{:?}:{}",
+                    row.row, row.group_idx, row.item_idx, row.text
+                )
+            }
+        }
+
+        let component = GroupMultiSelect::new()
+            .with_theme(&RowTheme)
+            .group("A", vec!["a1"])
+            .group("B", vec!["b1"])
+            .component((5, 80))
+            .unwrap();
+
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(
+            &lines[lines.len() - 4..],
+            [
+                "0:0:None:A",
+                "1:0:Some(0):a1",
+                "2:1:None:B",
+                "3:1:Some(0):b1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warning_toggle_behavior() {
+        let warning = ItemState::Warning {
+            message: "risky".to_string(),
+        };
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states("A", vec![("w1", warning)])
+            .warning_toggle_behavior(WarningToggle::BlockUntilAcknowledged);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.status.as_deref(), Some("risky (press y to confirm)"));
+        gs.handle_key(&mut state, Key::Char('n'), false);
+        assert_eq!(state.checked, vec![vec![false]]);
+
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, Key::Char('y'), false);
+        assert_eq!(state.checked, vec![vec![true]]);
+
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![true]]);
+        assert_eq!(state.status, None);
+    }
+
+    #[test]
+    fn test_rendered_height() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .show_scroll_indicators(false);
+        assert_eq!(gs.rendered_height(10), 6);
+        assert_eq!(gs.rendered_height(3), 4);
+
+        let gs = gs.focus_tooltip(true);
+        assert_eq!(gs.rendered_height(20), 7);
+        let frame = gs.component((24, 80)).unwrap().frame().unwrap();
+        assert_eq!(frame.lines.len(), 7);
+
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1", "b2", "b3"])
+            .auto_expand_on_focus(true);
+        assert_eq!(gs.rendered_height(20), 5);
+        let frame = gs.component((24, 80)).unwrap().frame().unwrap();
+        assert_eq!(frame.lines.len(), 5);
+    }
+
+    #[test]
+    fn test_select_all_skips_hidden_items() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["test_a", "a2"])
+            .group("B", vec!["test_b"])
+            .visible_when(|_, _, item, _, _| item.starts_with("test"));
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        gs.refresh_hidden(&mut state.view, &state.checked);
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![true]]);
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![false, false], vec![false]]);
+    }
+
+    #[test]
+    fn test_action_key() {
+        let mut component = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .action_key(Key::Char('+'))
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::Char(' '));
+        match component.handle_key(Key::Char('+')) {
+            ComponentEvent::ActionRequested { selection } => {
+                assert_eq!(selection.selection, vec![vec![0]])
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .report_counts(true);
+        let mut render = TermThemeRenderer::buffered(gs.theme);
+
+        gs.render_report(&mut render, &[vec![true, true], vec![false]], 80)
+            .unwrap();
+        assert_eq!(
+            render.into_lines()[0],
+            "Selected 2 of 3 items across 1 groups."
+        );
+    }
+
+    #[test]
+    fn test_header_enter_toggle_collapse() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .header_enter(HeaderEnter::ToggleCollapse)
+            .with_keybindings(KeyBindings {
+                confirm: vec![Key::Tab],
+                ..KeyBindings::default()
+            });
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Continue
+        ));
+        assert!(gs.is_collapsed(0));
+        gs.handle_key(&mut state, Key::Enter, false);
+        assert!(!gs.is_collapsed(0));
+
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Tab, false),
+            Step::Accept
+        ));
+    }
+
+    #[test]
+    fn test_on_start() {
+        let component = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .on_start(|checked, groups| {
+                groups.push(Group::new("B", vec!["b1", "b2"]));
+                checked[0][0] = true;
+            })
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        let outcome = component.outcome();
+        assert_eq!(outcome.selection, vec![vec![0], vec![]]);
+        assert!(!outcome.was_modified);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut gs: GroupMultiSelect<'_, &str> =
+            GroupMultiSelect::new().group("A", vec!["a1", "a2"]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![true, true]]);
+
+        gs.handle_key(&mut state, UNDO_KEY, false);
+        assert_eq!(state.checked, vec![vec![true, false]]);
+        gs.handle_key(&mut state, UNDO_KEY, false);
+        gs.handle_key(&mut state, UNDO_KEY, false);
+        assert_eq!(state.checked, vec![vec![false, false]]);
+
+        gs.handle_key(&mut state, REDO_KEY, false);
+        assert_eq!(state.checked, vec![vec![true, false]]);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, REDO_KEY, false);
+        assert_eq!(state.checked, vec![vec![false, false]]);
+    }
+
+    #[test]
+    fn test_from_grouped_by() {
+        let gs = GroupMultiSelect::from_grouped_by(vec!["b1", "a1", "b2", "c1"], |item| {
+            item[..1].to_string()
+        });
+        let groups: Vec<(&str, Vec<&str>)> = gs
+            .groups
+            .iter()
+            .map(|group| (group.label.as_str(), group.items.clone()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("b", vec!["b1", "b2"]),
+                ("a", vec!["a1"]),
+                ("c", vec!["c1"])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violation_marker() {
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        render
+            .group_multi_select_row(&RowContext {
+                group_idx: 0,
+                item_idx: None,
+                text: "A",
+                item_state: None,
+                group_state: GroupState::None,
+                checked: false,
+                active: false,
+                in_active_group: false,
+                collapsed: false,
+                counts: (0, 0),
+                show_counts: false,
+                constraint_ok: false,
+                recently_changed: false,
+                changed: false,
+                level: None,
+                accent: None,
+                label_match: None,
+                quick_key: None,
+                disabled_reason: None,
+                row: 0,
+            })
+            .unwrap();
+        assert_eq!(render.into_lines(), vec!["  ○ A !"]);
+    }
+
+    #[test]
+    fn test_backspace_clears_group() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    ("a2", ItemState::Normal),
+                    ("a3", disabled),
+                ],
+            )
+            .group("B", vec!["b1"])
+            .defaults(vec![vec![true, false, true], vec![true]]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::Backspace, false);
+        assert_eq!(state.checked, vec![vec![false, false, true], vec![true]]);
+        gs.handle_key(&mut state, Key::Backspace, false);
+        assert_eq!(state.checked, vec![vec![false, false, true], vec![true]]);
+    }
+
+    #[test]
+    fn test_report_overflow() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["alpha", "bravo", "charlie", "delta"]);
+        let checked = [vec![true; 4]];
+
+        let mut render = TermThemeRenderer::buffered(gs.theme);
+        gs.render_report(&mut render, &checked, 24).unwrap();
+        assert_eq!(
+            render.into_lines(),
+            vec!["Pick: alpha, bravo", "      charlie, delta"]
+        );
+
+        let gs = gs.report_overflow(ReportOverflow::Truncate);
+        let mut render = TermThemeRenderer::buffered(gs.theme);
+        gs.render_report(&mut render, &checked, 24).unwrap();
+        assert_eq!(render.into_lines(), vec!["Pick: alpha, +3 more"]);
+    }
+
+    #[test]
+    fn test_report_overflow_empty_selection() {
+        let prompt = "Pick from a list that is far wider than the terminal";
+        let checked = [vec![false; 2]];
+        for overflow in [ReportOverflow::Wrap, ReportOverflow::Truncate] {
+            let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+                .with_prompt(prompt)
+                .group("A", vec!["alpha", "bravo"])
+                .report_overflow(overflow);
+            let mut render = TermThemeRenderer::buffered(gs.theme);
+            gs.render_report(&mut render, &checked, 24).unwrap();
+            let lines = render.into_lines();
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].starts_with(prompt));
+        }
+    }
+
+    #[test]
+    fn test_spacing() {
+        let spacing = SpacingConfig {
+            after_prompt: true,
+            after_list: true,
+        };
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .spacing(spacing);
+        assert_eq!(gs.rendered_height(10), 6);
+
+        let lines = gs.component((5, 80)).unwrap().frame().unwrap().lines;
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[1], "");
+        assert_eq!(lines[4], "");
+    }
+
+    #[test]
+    fn test_search_query_echo() {
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1"])
+            .search(true)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        for key in [
+            Key::Char('/'),
+            Key::Char('d'),
+            Key::Char('e'),
+            Key::Char('x'),
+        ] {
+            component.handle_key(key);
+        }
+        component.handle_key(Key::Backspace);
+        assert_eq!(component.frame().unwrap().lines[0], "Pick: /de|");
+
+        assert_eq!(component.handle_key(Key::Escape), ComponentEvent::Pending);
+        assert_eq!(component.frame().unwrap().lines[0], "Pick:");
+        assert_eq!(GroupMultiSelect::<&str>::clamp_query("abcdef", 4), "…def");
+    }
+
+    #[test]
+    fn test_unselected() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    ("a2", disabled),
+                    ("a3", ItemState::Normal),
+                ],
+            )
+            .group("B", vec!["b1"]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        let outcome = gs.build_outcome(&state);
+        assert_eq!(outcome.selection, vec![vec![0], vec![]]);
+        assert_eq!(gs.unselected(&outcome), vec![vec![2], vec![0]]);
+    }
+
+    #[test]
+    fn test_group_subtitle() {
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group_with_subtitle("A", "first", vec!["a1"])
+            .group("B", vec!["b1", "b2"])
+            .show_scroll_indicators(false)
+            .component((4, 80))
+            .unwrap();
+
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "    first");
+
+        component.handle_key(Key::ArrowDown);
+        assert_eq!(component.frame().unwrap().lines[3], ">   ☐ a1");
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::ArrowDown);
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3], ">   ☐ b1");
+    }
+
+    #[test]
+    fn test_unbound_key_keeps_frame() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group("A", vec!["a1"]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        let frame = gs.frame_lines(&state).unwrap();
+
+        gs.handle_key(&mut state, Key::Char('z'), false);
+        assert_eq!(gs.frame_lines(&state).unwrap(), frame);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_ne!(gs.frame_lines(&state).unwrap(), frame);
+    }
+
+    #[test]
+    fn test_double_confirm_status_fits_page() {
+        let items: Vec<usize> = (0..30).collect();
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", items)
+            .double_confirm(DoubleConfirmWhen::Always)
+            .component((10, 80))
+            .unwrap();
+
+        assert_eq!(component.handle_key(Key::Enter), ComponentEvent::Pending);
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.last().unwrap(), "Press Enter again to confirm");
+        assert_eq!(lines.len(), 9);
+    }
+
+    #[test]
+    fn test_confirm_warnings_status_fits_page() {
+        let warning = ItemState::Warning {
+            message: "slow".to_string(),
+        };
+        let items: Vec<(usize, ItemState)> = (0..30).map(|i| (i, warning.clone())).collect();
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group_with_states("A", items)
+            .defaults(vec![vec![true]])
+            .confirm_warnings(true)
+            .component((10, 80))
+            .unwrap();
+
+        assert_eq!(component.handle_key(Key::Enter), ComponentEvent::Pending);
+        let lines = component.frame().unwrap().lines;
+        assert!(lines.last().unwrap().ends_with("Accept? (y/n)"));
+        assert_eq!(lines.len(), 9);
+    }
+
+    #[test]
+    fn test_warning_toggle_status_fits_page() {
+        let warning = ItemState::Warning {
+            message: "risky".to_string(),
+        };
+        let items: Vec<(usize, ItemState)> = (0..30).map(|i| (i, warning.clone())).collect();
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group_with_states("A", items)
+            .warning_toggle_behavior(WarningToggle::ConfirmEach)
+            .component((10, 80))
+            .unwrap();
+
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::Char(' '));
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.last().unwrap(), "risky (press y to confirm)");
+        assert_eq!(lines.len(), 9);
+    }
+
+    #[test]
+    fn test_double_confirm() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .double_confirm(DoubleConfirmWhen::WhenEmpty);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Continue
+        ));
+        assert_eq!(
+            state.status.as_deref(),
+            Some("Press Enter again to confirm")
+        );
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Continue
+        ));
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Accept
+        ));
+
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Accept
+        ));
+    }
+
+    #[test]
+    fn test_disabled_navigation() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let items = vec![("a1", disabled), ("a2", ItemState::Normal)];
+
+        let mut gs: GroupMultiSelect<'_, &str> =
+            GroupMultiSelect::new().group_with_states("A", items.clone());
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(state.cursor.item_idx, Some(1));
+
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states("A", items)
+            .disabled_navigation(DisabledNav::Stop);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(state.cursor.item_idx, Some(0));
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![false, false]]);
+    }
+
+    #[test]
+    fn test_live_output() {
+        let mut output = Vec::new();
+        {
+            let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .live_output(&mut output);
+            let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+            for key in [Key::ArrowDown, Key::Char(' '), Key::Char('a')] {
+                let before = state.checked.clone();
+                gs.handle_key(&mut state, key, false);
+                gs.emit_live(&state, &before).unwrap();
+            }
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "0:0\n0:0,1\n");
+    }
+
+    #[test]
+    fn test_toggle_flash() {
+        let mut component = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .toggle_flash(true)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::Char(' '));
+        assert_eq!(component.frame().unwrap().lines[2], ">   ☑ a1 •");
+        assert!(!component.poll());
+
+        thread::sleep(FLASH_DURATION);
+        assert!(component.poll());
+        assert_eq!(component.frame().unwrap().lines[2], ">   ☑ a1");
+    }
+
+    #[test]
+    fn test_set_item_state() {
+        let warning = ItemState::Warning {
+            message: "old".to_string(),
+        };
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .set_item_state(0, 1, warning.clone())
+            .unwrap();
+        assert_eq!(gs.groups[0].states, vec![ItemState::Normal, warning]);
+
+        assert!(gs.set_item_state(0, 2, ItemState::Normal).is_err());
+    }
+
+    #[test]
+    fn test_quick_keys() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group_with_states("B", vec![("b1", disabled), ("b2", ItemState::Normal)])
+            .quick_keys(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        let targets: Vec<char> = gs.quick_key_targets(&state).iter().map(|t| t.0).collect();
+        assert_eq!(targets, vec!['1', '2', '3']);
+
+        gs.handle_key(&mut state, Key::Char('3'), false);
+        assert_eq!(state.checked, vec![vec![false, false], vec![false, true]]);
+        gs.handle_key(&mut state, Key::Char('1'), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![false, true]]);
+        gs.handle_key(&mut state, Key::Char('9'), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![false, true]]);
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        let lines = render.into_lines();
+        assert_eq!(lines[3], "[2]     ☐ a2");
+        assert!(!lines[5].starts_with('['));
+    }
+
+    #[test]
+    fn test_quick_keys_skip_bound_keys() {
+        let hints = |gs: GroupMultiSelect<'_, String>| {
+            let state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+            let hints: String = gs.quick_key_targets(&state).iter().map(|t| t.0).collect();
+            hints
+        };
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", (1..=20).map(|i| i.to_string()).collect())
+                .quick_keys(true)
+        };
 
-                    if self.report {
-                        self.render_report(&mut render, &checked)?;
-                    }
+        assert_eq!(hints(gs()), "123456789bcefmnoprst");
+        let custom = gs().with_keybindings(KeyBindings {
+            left: vec![Key::Char('b')],
+            reorder_down: vec![Key::Char('c')],
+            ..KeyBindings::default()
+        });
+        assert_eq!(hints(custom), "123456789efmnoprstuv");
+    }
 
-                    term.show_cursor()?;
-                    term.flush()?;
+    #[test]
+    fn test_continue_key() {
+        let mut component = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .continue_key(Key::Tab)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
 
-                    return Ok(Some(self.build_result(&checked)));
-                }
-                Key::Escape | Key::Char('q') if allow_quit => {
-                    if self.clear {
-                        render.clear()?;
-                    }
-                    term.show_cursor()?;
-                    term.flush()?;
-                    return Ok(None);
-                }
-                _ => {}
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::Char(' '));
+        match component.handle_key(Key::Tab) {
+            ComponentEvent::Accepted {
+                selection,
+                finalize,
+            } => {
+                assert_eq!(selection.selection, vec![vec![0]]);
+                assert!(!finalize);
             }
+            event => panic!("unexpected {:?}", event),
+        }
+        assert!(matches!(
+            component.handle_key(Key::Enter),
+            ComponentEvent::Accepted { finalize: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_filter_matches_groups() {
+        assert_eq!(
+            GroupMultiSelect::<&str>::find_query("Personal", "SON"),
+            Some((3, 6))
+        );
+        assert_eq!(GroupMultiSelect::<&str>::find_query("Personal", ""), None);
+        assert_eq!(
+            GroupMultiSelect::<&str>::find_query("Über", "üb"),
+            Some((0, 3))
+        );
+
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("Work", vec!["a1"])
+            .search(true)
+            .filter_matches_groups(true)
+            .with_theme(&MatchTheme)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+        for key in [Key::Char('/'), Key::Char('o'), Key::Char('r')] {
+            component.handle_key(key);
+        }
+        assert_eq!(component.frame().unwrap().lines[1], "> ○ W<or>k (0/1)");
 
-            render.clear()?;
+        struct MatchTheme;
+
+        impl Theme for MatchTheme {
+            fn format_group_multi_select_query_match(
+                &self,
+                f: &mut dyn std::fmt::Write,
+                text: &str,
+            ) -> std::fmt::Result {
+                write!(f, "<{}>", text)
+            }
         }
     }
 
-    fn total_rows(&self) -> usize {
-        self.groups.iter().map(|g| 1 + g.items.len()).sum()
+    #[test]
+    fn test_groups_per_page() {
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .group("C", vec!["c1"])
+            .groups_per_page(2)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].contains("[Page 1/2]"));
+
+        component.handle_key(Key::PageDown);
+        let lines = component.frame().unwrap().lines;
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains('C'));
+
+        component.handle_key(Key::PageDown);
+        component.handle_key(Key::ArrowUp);
+        assert!(component.frame().unwrap().lines[0].contains("[Page 1/2]"));
+
+        component.handle_key(Key::PageUp);
+        assert_eq!(component.frame().unwrap().lines.len(), 6);
     }
 
-    fn cursor_to_flat(&self, cursor: Cursor) -> usize {
-        let mut flat = 0;
-        for g_idx in 0..cursor.group_idx {
-            flat += 1 + self.groups[g_idx].items.len();
-        }
-        flat += match cursor.item_idx {
-            None => 0,
-            Some(i) => 1 + i,
-        };
-        flat
+    #[test]
+    fn test_map_selection() {
+        let items = vec![vec!["a1", "a2"], vec!["b1"]];
+        assert_eq!(
+            map_selection(&[vec![1], vec![0, 3], vec![0]], &items),
+            vec![vec![&"a2"], vec![&"b1"], vec![]]
+        );
     }
 
-    fn flat_to_cursor(&self, flat_idx: usize) -> Cursor {
-        let mut remaining = flat_idx;
-        for (g_idx, group) in self.groups.iter().enumerate() {
-            if remaining == 0 {
-                return Cursor {
-                    group_idx: g_idx,
-                    item_idx: None,
-                };
-            }
-            remaining -= 1;
-            if remaining < group.items.len() {
-                return Cursor {
-                    group_idx: g_idx,
-                    item_idx: Some(remaining),
-                };
+    #[test]
+    fn test_group_with_color() {
+        let gs = GroupMultiSelect::new()
+            .group_with_color("A", vec!["a1"], Color::Cyan)
+            .group("B", vec!["b1"])
+            .with_theme(&AccentTheme);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        state.cursor = gs.move_cursor_down(&state.view, state.cursor);
+
+        let mut render = TermThemeRenderer::buffered(&AccentTheme);
+        gs.render(&mut render, &state).unwrap();
+        assert_eq!(
+            render.into_lines()[1..],
+            ["  ○ (A) (0/1)", ">   ☐ (a1)", "  ○ B (0/1)", "    ☐ b1"]
+        );
+
+        struct AccentTheme;
+
+        impl Theme for AccentTheme {
+            fn format_group_multi_select_accent(
+                &self,
+                f: &mut dyn std::fmt::Write,
+                text: &str,
+                _accent: Color,
+            ) -> std::fmt::Result {
+                write!(f, "({})", text)
             }
-            remaining -= group.items.len();
         }
-        Cursor::default()
     }
 
-    fn is_item_disabled(&self, cursor: Cursor) -> bool {
-        match cursor.item_idx {
-            None => false,
-            Some(item_idx) => {
-                matches!(
-                    self.groups[cursor.group_idx].states.get(item_idx),
-                    Some(ItemState::Disabled { .. })
+    #[test]
+    fn test_quit_returns_progress() {
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .quit_returns_progress(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        state.checked[0][1] = true;
+
+        let outcome = gs.progress(&state);
+        assert_eq!(outcome.selection, vec![vec![1]]);
+        assert!(outcome.cancelled);
+        assert!(!gs.build_outcome(&state).cancelled);
+
+        let selection = gs
+            .run_with_events(vec![Key::ArrowDown, Key::Char(' '), Key::Escape])
+            .unwrap();
+        assert_eq!(selection, Some(vec![vec![0]]));
+    }
+
+    #[test]
+    fn test_align_labels() {
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["a", "abc"])
+            .group("B", vec!["b1"])
+            .align_labels(true)
+            .mark_changes(true)
+            .defaults(vec![vec![true, true], vec![true]]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        state.checked = vec![vec![false, false], vec![false]];
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        let lines = render.into_lines();
+        let marker_columns: Vec<_> = [2, 3, 5]
+            .iter()
+            .map(|&row| lines[row].find('*').unwrap())
+            .collect();
+        assert_eq!(marker_columns, vec![marker_columns[0]; 3]);
+        assert_eq!(gs.label_width(&state.view, 8), 1);
+    }
+
+    #[test]
+    fn test_scroll_off() {
+        let items: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let gs = GroupMultiSelect::new()
+            .group("A", items)
+            .scroll_off(2)
+            .show_scroll_indicators(false);
+        let cursor = |item_idx| Cursor {
+            group_idx: 0,
+            item_idx: Some(item_idx),
+        };
+
+        assert_eq!(gs.adjust_page_offset(&View::default(), cursor(2), 0, 6), 0);
+        assert_eq!(gs.adjust_page_offset(&View::default(), cursor(3), 0, 6), 1);
+        assert_eq!(gs.adjust_page_offset(&View::default(), cursor(10), 8, 6), 8);
+        assert_eq!(
+            gs.adjust_page_offset(&View::default(), cursor(10), 10, 6),
+            9
+        );
+        assert_eq!(
+            gs.adjust_page_offset(&View::default(), cursor(19), 14, 6),
+            15
+        );
+        assert_eq!(
+            gs.scroll_off(10)
+                .adjust_page_offset(&View::default(), cursor(3), 0, 6),
+            1
+        );
+    }
+
+    #[test]
+    fn test_group_specs() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let gs = GroupMultiSelect::new().group("A", vec!["a1"]).group_specs(
+            "B",
+            vec![
+                ItemSpec::new("b1").checked(true).description("first"),
+                ItemSpec::new("b2").state(disabled.clone()),
+            ],
+        );
+
+        assert_eq!(gs.initial_checked(), vec![vec![false], vec![true, false]]);
+        assert_eq!(gs.groups[1].states, vec![ItemState::Normal, disabled]);
+        let cursor = |item_idx| Cursor {
+            group_idx: 1,
+            item_idx: Some(item_idx),
+        };
+        assert_eq!(gs.tooltip(cursor(0)), Some("first"));
+        assert_eq!(gs.tooltip(cursor(1)), Some("fixed"));
+    }
+
+    #[test]
+    fn test_initial_page_offset() {
+        let items: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let offset = |offset, reveal| {
+            let mut gs = GroupMultiSelect::new()
+                .group("A", items.clone())
+                .max_length(5)
+                .reveal(0, reveal)
+                .initial_page_offset(offset)
+                .show_scroll_indicators(false);
+            gs.start(TEST_TERM_SIZE).unwrap().page_offset
+        };
+
+        assert_eq!(offset(8, 10), 8);
+        assert_eq!(offset(2, 10), 7);
+        assert_eq!(offset(100, 19), 16);
+    }
+
+    #[test]
+    fn test_item_tab() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .group_with_states("B", vec![("b1", disabled), ("b2", ItemState::Normal)])
+            .item_tab(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        let position = |state: &State| (state.cursor.group_idx, state.cursor.item_idx);
+
+        gs.handle_key(&mut state, Key::Tab, false);
+        assert_eq!(position(&state), (0, Some(0)));
+        gs.handle_key(&mut state, Key::Tab, false);
+        assert_eq!(position(&state), (1, Some(1)));
+        gs.handle_key(&mut state, Key::Tab, false);
+        assert_eq!(position(&state), (0, Some(0)));
+        gs.handle_key(&mut state, Key::BackTab, false);
+        assert_eq!(position(&state), (1, Some(1)));
+    }
+
+    #[test]
+    fn test_group_prologue() {
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1"])
+            .group("B", vec!["b1"])
+            .group_prologue(1, || vec!["About B".to_string(), "---".to_string()])
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::ArrowDown);
+        assert_eq!(
+            component.frame().unwrap().lines,
+            vec![
+                "Pick:",
+                "  ○ A (0/1)",
+                "    ☐ a1",
+                "About B",
+                "---",
+                "> ○ B (0/1)",
+                "    ☐ b1"
+            ]
+        );
+        assert_eq!(
+            component.prompt.layout(&component.state.view).lines(0..4),
+            6
+        );
+    }
+
+    #[test]
+    fn test_auto_deselect_on_disable() {
+        let disabled = ItemState::Disabled {
+            reason: "off".to_string(),
+        };
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .recompute_states_in_background(move |checked| {
+                let state = if checked[0][1] {
+                    disabled.clone()
+                } else {
+                    ItemState::Normal
+                };
+                vec![vec![state, ItemState::Normal]]
+            });
+        let selection = gs
+            .run_with_events(vec![
+                Key::ArrowDown,
+                Key::Char(' '),
+                Key::ArrowDown,
+                Key::Char(' '),
+                Key::Enter,
+            ])
+            .unwrap();
+        assert_eq!(selection, Some(vec![vec![1]]));
+
+        for (auto_deselect, expected) in [
+            (true, vec![vec![true, false]]),
+            (false, vec![vec![true; 2]]),
+        ] {
+            let gs = GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .defaults(vec![vec![true, true]])
+                .auto_deselect_on_disable(auto_deselect)
+                .set_item_state(
+                    0,
+                    1,
+                    ItemState::Disabled {
+                        reason: "off".to_string(),
+                    },
                 )
-            }
+                .unwrap();
+            assert_eq!(gs.initial_checked(), expected);
         }
     }
 
-    fn move_cursor_down(&self, cursor: Cursor) -> Cursor {
-        let total = self.total_rows();
-        let mut flat = self.cursor_to_flat(cursor);
+    #[test]
+    fn test_clone_config() {
+        let base = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .defaults(vec![vec![true]])
+            .mark_changes(true);
+        let variant = base.clone_config().group("B", vec!["b1"]);
 
-        loop {
-            if flat + 1 >= total {
-                return cursor;
-            }
-            flat += 1;
-            let new_cursor = self.flat_to_cursor(flat);
-            if !self.is_item_disabled(new_cursor) {
-                return new_cursor;
-            }
-        }
+        assert_eq!(base.groups.len(), 1);
+        assert_eq!(variant.groups.len(), 2);
+        assert_eq!(variant.prompt, "Pick");
+        assert!(variant.mark_changes);
+        assert_eq!(
+            variant.initial_checked(),
+            vec![vec![true, false], vec![false]]
+        );
     }
 
-    fn move_cursor_up(&self, cursor: Cursor) -> Cursor {
-        let mut flat = self.cursor_to_flat(cursor);
+    #[test]
+    fn test_select_visible() {
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1", "b2"])
+            .max_length(3)
+            .show_scroll_indicators(false);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
 
-        loop {
-            if flat == 0 {
-                return cursor;
-            }
-            flat -= 1;
-            let new_cursor = self.flat_to_cursor(flat);
-            if !self.is_item_disabled(new_cursor) {
-                return new_cursor;
-            }
+        gs.handle_key(&mut state, Key::Char('A'), false);
+        assert_eq!(state.checked, vec![vec![true, true], vec![false, false]]);
+        gs.handle_key(&mut state, Key::Char('A'), false);
+        assert_eq!(state.checked, vec![vec![false, false], vec![false, false]]);
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![true, true], vec![true, true]]);
+    }
+
+    #[test]
+    fn test_selection_hash() {
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["x", "y"])
+            .group("B", vec!["x"]);
+        let reordered = GroupMultiSelect::new()
+            .group("B", vec!["x"])
+            .group("A", vec!["y", "x"]);
+
+        let hash = gs.selection_hash(&[vec![1, 0], vec![0]]);
+        assert_eq!(hash, reordered.selection_hash(&[vec![0], vec![1, 0]]));
+        assert_eq!(hash, gs.selection_hash(&[vec![0, 1, 7], vec![0]]));
+        assert_ne!(hash, gs.selection_hash(&[vec![0, 1], vec![]]));
+        assert_ne!(
+            gs.selection_hash(&[vec![0], vec![]]),
+            gs.selection_hash(&[vec![], vec![0]])
+        );
+        assert_eq!(gs.selection_hash(&[]), FNV_OFFSET_BASIS);
+        assert_eq!(gs.selection_hash(&[vec![0], vec![]]), 0x53db_7288_ac1c_1e74);
+    }
+
+    #[test]
+    fn test_selection_limits() {
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2", "a3"])
+            .group("B", vec!["b1"])
+            .max_selections(2)
+            .group_min(1, 1);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![true, true, false], vec![false]]);
+
+        state.cursor = gs.move_cursor_down(&state.view, state.cursor);
+        state.cursor = gs.move_cursor_down(&state.view, state.cursor);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![true, false, false], vec![false]]);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![true, false, true], vec![false]]);
+        assert_eq!(
+            state.error.as_deref(),
+            Some("Selection limit reached (2/2)")
+        );
+
+        assert!(matches!(
+            gs.handle_key(&mut state, Key::Enter, false),
+            Step::Continue
+        ));
+        assert_eq!(state.error.as_deref(), Some("Select at least 1 item in B"));
+        assert!(!gs.group_within_limits(1, &state.checked[1]));
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        assert_eq!(
+            render.into_lines().last().unwrap(),
+            "error: Select at least 1 item in B"
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let disabled = || ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1".to_string(), "a2".to_string()])
+            .group_with_states(
+                "B",
+                vec![
+                    ("b1".to_string(), disabled()),
+                    ("b2".to_string(), disabled()),
+                ],
+            );
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(
+            gs.values(&gs.build_outcome(&state)),
+            vec![vec!["a1".to_string(), "a2".to_string()], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_search_filters_items() {
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["apple", "avocado"])
+            .group("B", vec!["banana", "blueberry"])
+            .group("C", vec!["cherry"])
+            .search(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        for key in [Key::Char('/'), Key::Char('E'), Key::Char('r')] {
+            gs.handle_key(&mut state, key, false);
         }
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        let lines = render.into_lines();
+        assert!(lines.iter().all(|line| !line.contains('A')));
+        assert!(lines.iter().any(|line| line.ends_with("○ B (0/2)")));
+        assert!(lines.iter().any(|line| line.ends_with("☐ blueberry")));
+        assert!(lines.iter().all(|line| !line.contains("banana")));
+        assert!(lines.iter().any(|line| line.ends_with("☐ cherry")));
+        assert_eq!(gs.total_rows(&state.view), 4);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(
+            state.checked,
+            vec![vec![false, false], vec![false, true], vec![false]]
+        );
+
+        gs.handle_key(&mut state, Key::Char('x'), false);
+        assert_eq!(gs.total_rows(&state.view), 0);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(
+            state.checked,
+            vec![vec![false, false], vec![false, true], vec![false]]
+        );
+        gs.handle_key(&mut state, Key::Backspace, false);
+        assert_eq!(gs.total_rows(&state.view), 4);
+
+        gs.handle_key(&mut state, Key::Escape, false);
+        assert_eq!(state.query, None);
+        assert_eq!(gs.total_rows(&state.view), 8);
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(1))
+        );
     }
 
-    fn toggle(&self, checked: &mut [Vec<bool>], cursor: Cursor) {
-        match cursor.item_idx {
-            None => {
-                let group = &self.groups[cursor.group_idx];
-                if group.items.is_empty() {
-                    return;
-                }
-                let selectable_all_selected = group
-                    .states
-                    .iter()
-                    .zip(checked[cursor.group_idx].iter())
-                    .filter(|(state, _)| !matches!(state, ItemState::Disabled { .. }))
-                    .all(|(_, &is_checked)| is_checked);
-                let new_state = !selectable_all_selected;
-                for (idx, state) in group.states.iter().enumerate() {
-                    if !matches!(state, ItemState::Disabled { .. }) {
-                        checked[cursor.group_idx][idx] = new_state;
+    #[test]
+    fn test_collapsed_count() {
+        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_collapsed("A", vec!["a1", "a2", "a3"], true)
+            .group("B", vec!["b1"])
+            .defaults(vec![vec![true, false, true], vec![true]])
+            .show_group_counts(false);
+        let state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        let lines = render.into_lines();
+        assert_eq!(lines[1], "> ◐ A (2/3) …");
+        assert_eq!(lines[2], "  ◉ B");
+    }
+
+    #[test]
+    fn test_interact_with_keys() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .group("B", vec!["b1"])
+        };
+
+        let keys = [Key::ArrowDown, Key::ArrowDown, Key::Char(' ')];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![1], vec![]]));
+
+        let keys = [Key::Char(' '), Key::Enter, Key::Char(' ')];
+        let selection = gs().interact_with_keys(keys).unwrap();
+        assert_eq!(selection, Some(vec![vec![0, 1], vec![]]));
+
+        assert_eq!(gs().interact_with_keys([Key::Escape]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_interact_with_keys_skips_confirm_steps() {
+        let keys = [Key::ArrowDown, Key::Char(' ')];
+        let selection = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .review_step(true)
+            .interact_with_keys(keys)
+            .unwrap();
+        assert_eq!(selection, Some(vec![vec![0]]));
+
+        let selection = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .defaults(vec![vec![true, false]])
+            .header_enter(HeaderEnter::ToggleCollapse)
+            .interact_with_keys([])
+            .unwrap();
+        assert_eq!(selection, Some(vec![vec![0]]));
+
+        let refused = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .min_selections(1)
+            .interact_with_keys([]);
+        assert!(refused.is_err());
+    }
+
+    #[test]
+    fn test_validate_with() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("Features", vec!["default", "experiments"])
+                .validate_with(|checked| {
+                    if checked[0][1] && !checked[0][0] {
+                        Err("experiments requires default".to_string())
+                    } else {
+                        Ok(())
                     }
-                }
-            }
-            Some(item_idx) => {
-                if !matches!(
-                    self.groups[cursor.group_idx].states.get(item_idx),
-                    Some(ItemState::Disabled { .. })
-                ) {
-                    checked[cursor.group_idx][item_idx] = !checked[cursor.group_idx][item_idx];
-                }
-            }
+                })
+        };
+
+        let mut component = gs().component(TEST_TERM_SIZE).unwrap();
+        for key in [Key::ArrowDown, Key::ArrowDown, Key::Char(' '), Key::Enter] {
+            assert!(matches!(component.handle_key(key), ComponentEvent::Pending));
         }
+        let lines = component.frame().unwrap().lines;
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("experiments requires default")));
+
+        let keys = [Key::ArrowDown, Key::ArrowDown, Key::Char(' '), Key::Enter];
+        let keys = keys.into_iter().chain([Key::ArrowUp, Key::Char(' ')]);
+        assert_eq!(
+            gs().interact_with_keys(keys).unwrap(),
+            Some(vec![vec![0, 1]])
+        );
     }
 
-    fn group_state(checked: &[bool]) -> GroupState {
-        let selected_count = checked.iter().filter(|&&b| b).count();
-        let total = checked.len();
-        if total == 0 || selected_count == 0 {
-            GroupState::None
-        } else if selected_count == total {
-            GroupState::All
-        } else {
-            GroupState::Partial
+    #[test]
+    fn test_invert_selection() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let warning = ItemState::Warning {
+            message: "slow".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    ("a2", warning),
+                    ("a3", disabled.clone()),
+                ],
+            )
+            .group_with_states("B", vec![("b1", disabled), ("b2", ItemState::Normal)])
+            .defaults(vec![vec![true, false, true], vec![false, false]]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::Char('i'), false);
+        assert_eq!(
+            state.checked,
+            vec![vec![false, true, true], vec![false, true]]
+        );
+        gs.handle_key(&mut state, Key::Char('i'), false);
+        assert_eq!(
+            state.checked,
+            vec![vec![true, false, true], vec![false, false]]
+        );
+    }
+
+    #[test]
+    fn test_home_and_end() {
+        let disabled = || ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group_with_states("A", vec![("a1", disabled()), ("a2", ItemState::Normal)])
+            .group_with_states("B", vec![("b1", ItemState::Normal), ("b2", disabled())])
+            .group_with_states("C", vec![("c1", disabled())])
+            .search(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        gs.handle_key(&mut state, Key::End, false);
+        assert_eq!((state.cursor.group_idx, state.cursor.item_idx), (2, None));
+        gs.handle_key(&mut state, Key::Home, false);
+        assert_eq!((state.cursor.group_idx, state.cursor.item_idx), (0, None));
+
+        let mut gs = GroupMultiSelect::new()
+            .group_with_states("A", vec![("a1", disabled()), ("a2", ItemState::Normal)])
+            .group_with_states("B", vec![("b1", ItemState::Normal), ("b2", disabled())])
+            .search(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        gs.handle_key(&mut state, Key::End, false);
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(0))
+        );
+
+        for key in [Key::Char('/'), Key::Char('x'), Key::Home] {
+            gs.handle_key(&mut state, key, false);
+        }
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(0))
+        );
+    }
+
+    #[test]
+    fn test_page_down_and_up() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    ("a2", ItemState::Normal),
+                    ("a3", disabled),
+                    ("a4", ItemState::Normal),
+                ],
+            )
+            .group("B", vec!["b1", "b2"])
+            .max_length(3)
+            .show_scroll_indicators(false);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        let at = |state: &State| (state.cursor.group_idx, state.cursor.item_idx);
+
+        gs.handle_key(&mut state, Key::PageDown, false);
+        assert_eq!(at(&state), (0, Some(3)));
+        assert_eq!(state.page_offset, 2);
+        gs.handle_key(&mut state, Key::PageDown, false);
+        assert_eq!(at(&state), (1, Some(1)));
+        gs.handle_key(&mut state, Key::PageDown, false);
+        assert_eq!(at(&state), (1, Some(1)));
+        assert_eq!(state.page_offset, 5);
+
+        gs.handle_key(&mut state, Key::PageUp, false);
+        assert_eq!(at(&state), (0, Some(3)));
+        gs.handle_key(&mut state, Key::PageUp, false);
+        assert_eq!(at(&state), (0, Some(0)));
+        gs.handle_key(&mut state, Key::PageUp, false);
+        assert_eq!(at(&state), (0, None));
+        assert_eq!(state.page_offset, 0);
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .group_with_states("B", vec![("b1", ItemState::Normal), ("b2", disabled)])
+            .wrap_around(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        let at = |state: &State| (state.cursor.group_idx, state.cursor.item_idx);
+
+        gs.handle_key(&mut state, Key::ArrowUp, false);
+        assert_eq!(at(&state), (1, Some(0)));
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(at(&state), (0, None));
+
+        let mut gs = GroupMultiSelect::new()
+            .group("A", Vec::<&str>::new())
+            .wrap_around(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(at(&state), (0, None));
+    }
+
+    #[test]
+    fn test_select_group() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .group_with_states(
+                "B",
+                vec![
+                    ("b1", ItemState::Normal),
+                    ("b2", disabled),
+                    ("b3", ItemState::Normal),
+                ],
+            )
+            .defaults(vec![vec![false], vec![false, false, true]]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        for key in [Key::ArrowDown, Key::ArrowDown, Key::ArrowDown] {
+            gs.handle_key(&mut state, key, false);
         }
+        gs.handle_key(&mut state, Key::Char('S'), false);
+        assert_eq!(state.checked, vec![vec![false], vec![true, false, true]]);
+        gs.handle_key(&mut state, Key::Char('S'), false);
+        assert_eq!(state.checked, vec![vec![false], vec![true, false, true]]);
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (1, Some(0))
+        );
+    }
+
+    #[test]
+    fn test_remapped_keys() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .with_keybindings(KeyBindings {
+                    toggle: vec![Key::Char('x')],
+                    move_down: vec![Key::Char('n')],
+                    ..KeyBindings::plain()
+                })
+        };
+
+        let keys = [
+            Key::Char('n'),
+            Key::Char('x'),
+            Key::Char('j'),
+            Key::Char(' '),
+        ];
+        assert_eq!(gs().interact_with_keys(keys).unwrap(), Some(vec![vec![0]]));
+        assert_eq!(
+            gs().interact_with_keys([Key::Char('q')]).unwrap(),
+            Some(vec![vec![]])
+        );
+        assert_eq!(gs().interact_with_keys([Key::Escape]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_remapped_confirm() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .with_keybindings(KeyBindings {
+                    confirm: vec![Key::Char('y')],
+                    ..KeyBindings::default()
+                })
+        };
+
+        let keys = vec![Key::ArrowDown, Key::Char(' '), Key::Enter];
+        assert!(gs().run_with_events(keys).is_err());
+        let keys = vec![Key::ArrowDown, Key::Char(' '), Key::Enter, Key::Char('y')];
+        assert_eq!(gs().run_with_events(keys).unwrap(), Some(vec![vec![0]]));
     }
 
-    fn adjust_page_offset(&self, cursor: Cursor, current_offset: usize, capacity: usize) -> usize {
-        let flat = self.cursor_to_flat(cursor);
-        let total = self.total_rows();
+    #[test]
+    fn test_remapped_item_keys() {
+        let mut gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    (
+                        "a2",
+                        ItemState::Warning {
+                            message: "risky".to_string(),
+                        },
+                    ),
+                    ("a3", ItemState::Normal),
+                ],
+            )
+            .reorderable(true)
+            .search(true)
+            .warning_toggle_behavior(WarningToggle::ConfirmEach)
+            .with_keybindings(KeyBindings {
+                reorder_up: vec![Key::Char('u')],
+                reorder_down: vec![Key::Char('m')],
+                search: vec![Key::Char('f')],
+                clear_group: vec![Key::Char('c')],
+                accept_warning: vec![Key::Char('o')],
+                ..KeyBindings::default()
+            });
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
 
-        if capacity >= total {
-            return 0;
+        for key in [Key::ArrowDown, Key::Char('J'), Key::Char('m')] {
+            gs.handle_key(&mut state, key, false);
         }
+        assert_eq!(gs.groups[0].items, vec!["a2", "a1", "a3"]);
+        gs.handle_key(&mut state, Key::Char('u'), false);
+        assert_eq!(gs.groups[0].items, vec!["a1", "a2", "a3"]);
 
-        if flat < current_offset {
-            flat
-        } else if flat >= current_offset + capacity {
-            flat - capacity + 1
-        } else {
-            current_offset
-        }
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.status.as_deref(), Some("risky (press o to confirm)"));
+        gs.handle_key(&mut state, Key::Char('o'), false);
+        assert_eq!(state.checked, vec![vec![false, true, false]]);
+
+        gs.handle_key(&mut state, Key::Home, false);
+        gs.handle_key(&mut state, Key::Backspace, false);
+        assert_eq!(state.checked, vec![vec![false, true, false]]);
+        gs.handle_key(&mut state, Key::Char('c'), false);
+        assert_eq!(state.checked, vec![vec![false; 3]]);
+
+        gs.handle_key(&mut state, Key::Char('/'), false);
+        assert_eq!(state.query, None);
+        gs.handle_key(&mut state, Key::Char('f'), false);
+        assert_eq!(state.query.as_deref(), Some(""));
     }
 
-    fn render(
-        &self,
-        render: &mut TermThemeRenderer,
-        checked: &[Vec<bool>],
-        cursor: Cursor,
-        page_offset: usize,
-        capacity: usize,
-    ) -> Result<()> {
-        let total = self.total_rows();
-        let paging_info = if capacity < total {
-            let total_pages = (total + capacity - 1) / capacity;
-            let current_page = page_offset / capacity + 1;
-            Some((current_page, total_pages))
-        } else {
+    #[test]
+    fn test_keybinding_presets() {
+        let gs = |keybindings| {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1", "a2"])
+                .with_keybindings(keybindings)
+        };
+        let keys = [
+            Key::Char('j'),
+            Key::Char('x'),
+            Key::Char('h'),
+            Key::Char('q'),
+        ];
+
+        assert_eq!(
+            gs(KeyBindings::vim())
+                .run_with_events(keys.to_vec())
+                .unwrap(),
             None
+        );
+        assert_eq!(
+            gs(KeyBindings::vim())
+                .run_with_events(vec![keys[0].clone(), keys[1].clone(), Key::Enter])
+                .unwrap(),
+            Some(vec![vec![0]])
+        );
+        let mut plain_keys = keys.to_vec();
+        plain_keys.push(Key::Enter);
+        assert_eq!(
+            gs(KeyBindings::plain())
+                .run_with_events(plain_keys)
+                .unwrap(),
+            Some(vec![vec![]])
+        );
+    }
+
+    #[test]
+    fn test_initial_cursor() {
+        let disabled = ItemState::Disabled {
+            reason: "fixed".to_string(),
+        };
+        let start = |gs: GroupMultiSelect<'_, &str>| {
+            let component = gs.component(TEST_TERM_SIZE).unwrap();
+            let cursor = component.state.cursor;
+            (cursor.group_idx, cursor.item_idx)
+        };
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1"])
+                .group_with_states(
+                    "B",
+                    vec![("b1", disabled.clone()), ("b2", ItemState::Normal)],
+                )
         };
-        render.group_multi_select_prompt(&self.prompt, paging_info)?;
-        let visible_end = (page_offset + capacity).min(total);
 
-        for flat_idx in page_offset..visible_end {
-            let pos = self.flat_to_cursor(flat_idx);
-            let is_active = pos.group_idx == cursor.group_idx && pos.item_idx == cursor.item_idx;
+        assert_eq!(start(gs().initial_cursor(1, None)), (1, None));
+        assert_eq!(start(gs().initial_cursor(1, Some(0))), (1, Some(1)));
+        assert_eq!(start(gs().initial_cursor(7, Some(9))), (1, Some(1)));
+        assert_eq!(start(gs().focus_first_selected()), (0, None));
+        let gs = gs().defaults(vec![vec![false], vec![false, true]]);
+        assert_eq!(start(gs.focus_first_selected()), (1, Some(1)));
+    }
 
-            match pos.item_idx {
-                None => {
-                    let state = Self::group_state(&checked[pos.group_idx]);
-                    render.group_multi_select_header(
-                        &self.groups[pos.group_idx].label,
-                        state,
-                        is_active,
-                    )?;
-                }
-                Some(item_idx) => {
-                    let item_text = self.groups[pos.group_idx].items[item_idx].to_string();
-                    let is_checked = checked[pos.group_idx][item_idx];
-                    let state = &self.groups[pos.group_idx].states[item_idx];
-
-                    match state {
-                        ItemState::Normal => {
-                            render.group_multi_select_item(&item_text, is_checked, is_active)?;
-                        }
-                        ItemState::Disabled { reason } => {
-                            render
-                                .group_multi_select_item_disabled(&item_text, reason, is_active)?;
-                        }
-                        ItemState::Warning { message } => {
-                            render.group_multi_select_item_warning(
-                                &item_text, message, is_checked, is_active,
-                            )?;
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_detailed() {
+        let warning = ItemState::Warning {
+            message: "slow".to_string(),
+        };
+        let gs = GroupMultiSelect::new()
+            .group_with_states("A", vec![("a1", ItemState::Normal), ("a2", warning)])
+            .group("B", vec!["b1"])
+            .defaults(vec![vec![true, true], vec![false]]);
+        let outcome = gs.build_outcome(&gs.new_state(gs.initial_checked(), TEST_TERM_SIZE));
 
-        Ok(())
+        let detailed = gs.detailed(&outcome);
+        assert_eq!(detailed[0].label, "A");
+        assert_eq!(
+            detailed[0].selected,
+            vec![
+                SelectedItem {
+                    index: 0,
+                    value: "a1",
+                    had_warning: false
+                },
+                SelectedItem {
+                    index: 1,
+                    value: "a2",
+                    had_warning: true
+                },
+            ]
+        );
+        assert!(detailed[1].selected.is_empty());
     }
 
-    fn render_report(&self, render: &mut TermThemeRenderer, checked: &[Vec<bool>]) -> Result<()> {
-        let selected: Vec<String> = self
-            .groups
+    #[test]
+    fn test_confirm_warnings() {
+        let warning = ItemState::Warning {
+            message: "slow".to_string(),
+        };
+        let gs = || {
+            GroupMultiSelect::new()
+                .group_with_states(
+                    "A",
+                    vec![("a1", ItemState::Normal), ("a2", warning.clone())],
+                )
+                .defaults(vec![vec![false, true]])
+                .confirm_warnings(true)
+        };
+
+        let mut component = gs().component(TEST_TERM_SIZE).unwrap();
+        assert_eq!(component.handle_key(Key::Enter), ComponentEvent::Pending);
+        let lines = component.frame().unwrap().lines;
+        assert!(lines
             .iter()
-            .zip(checked.iter())
-            .flat_map(|(group, group_checked)| {
-                group
-                    .items
-                    .iter()
-                    .zip(group_checked.iter())
-                    .filter(|(_, &is_checked)| is_checked)
-                    .map(|(item, _)| item.to_string())
-            })
-            .collect();
+            .any(|line| line.contains("a2: slow. Accept? (y/n)")));
 
-        let selected_refs: Vec<&str> = selected.iter().map(|s| s.as_str()).collect();
-        render.group_multi_select_prompt_selection(&self.prompt, &selected_refs)?;
-        Ok(())
+        let keys = [Key::Enter, Key::Char('n'), Key::End, Key::Char(' ')];
+        assert_eq!(gs().interact_with_keys(keys).unwrap(), Some(vec![vec![]]));
+        let keys = [Key::Enter, Key::Char('y')];
+        assert_eq!(
+            gs().run_with_events(keys.to_vec()).unwrap(),
+            Some(vec![vec![1]])
+        );
     }
 
-    fn build_result(&self, checked: &[Vec<bool>]) -> Vec<Vec<usize>> {
-        checked
-            .iter()
-            .map(|group_checked| {
-                group_checked
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, &is_checked)| is_checked)
-                    .map(|(idx, _)| idx)
-                    .collect()
-            })
-            .collect()
+    #[test]
+    fn test_disabled_group() {
+        let mut gs = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .group_disabled("B", vec!["b1", "b2"], "no license")
+            .group("C", vec!["c1"])
+            .defaults(vec![vec![false], vec![true, false], vec![false]]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        let at = |state: &State| (state.cursor.group_idx, state.cursor.item_idx);
+
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(at(&state), (2, None));
+        gs.handle_key(&mut state, Key::ArrowUp, false);
+        assert_eq!(at(&state), (0, Some(0)));
+
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        gs.handle_key(&mut state, Key::Char('i'), false);
+        gs.handle_key(&mut state, Key::Char('i'), false);
+        assert_eq!(
+            state.checked,
+            vec![vec![true], vec![true, false], vec![true]]
+        );
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        let lines = render.into_lines();
+        assert!(lines[3].contains("⊘ B (no license)"));
+        assert!(lines[4].contains("b1 (no license)"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_show_count() {
+        let mut gs = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .max_selections(2)
+            .show_count(true);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+
+        let prompt = |gs: &GroupMultiSelect<'_, &str>, state: &State| {
+            let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+            gs.render(&mut render, state).unwrap();
+            render.into_lines().remove(0)
+        };
+        assert_eq!(prompt(&gs, &state), "Pick: (0/2 selected)");
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(prompt(&gs, &state), "Pick: (1/2 selected)");
+
+        let gs = gs.show_count(false);
+        assert!(!prompt(&gs, &state).contains("selected"));
+    }
 
     #[test]
-    fn test_cursor_conversion_roundtrip() {
-        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+    fn test_show_help() {
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", (1..=30).map(|i| i.to_string()).collect())
+                .search(true)
+                .show_scroll_indicators(false)
+        };
+        let frame = |gs: GroupMultiSelect<'_, String>| {
+            gs.component(TEST_TERM_SIZE).unwrap().frame().unwrap().lines
+        };
+
+        let plain = frame(gs());
+        let with_help = frame(gs().show_help(true));
+        assert_eq!(with_help.len(), plain.len());
+        assert_eq!(with_help[..with_help.len() - 1], plain[..plain.len() - 1]);
+        assert_eq!(
+            with_help.last().unwrap(),
+            "↑/k/↓/j move · space toggle · a all · / search · enter accept · esc/q quit"
+        );
+
+        let mut state = gs().new_state(vec![vec![false; 30]], TEST_TERM_SIZE);
+        state.allow_quit = false;
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs().show_help(true).render(&mut render, &state).unwrap();
+        assert_eq!(
+            render.into_lines().last().unwrap(),
+            "↑/k/↓/j move · space toggle · a all · / search · enter accept"
+        );
+
+        let custom = frame(gs().show_help(true).help_text("space to pick"));
+        assert_eq!(custom.last().unwrap(), "space to pick");
+    }
+
+    #[test]
+    fn test_locked_items() {
+        let mut gs = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![("a1", ItemState::Locked), ("a2", ItemState::Normal)],
+            )
+            .group_with_states("B", vec![("b1", ItemState::Locked)])
+            .defaults(vec![vec![false, false], vec![false]]);
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        assert_eq!(state.checked, vec![vec![true, false], vec![true]]);
+        assert_eq!(gs.header_state(1, &state.checked[1]), GroupState::All);
+
+        gs.handle_key(&mut state, Key::Char('i'), false);
+        assert_eq!(state.checked, vec![vec![true, true], vec![true]]);
+        gs.handle_key(&mut state, Key::Char('a'), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![true]]);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![true]]);
+        gs.handle_key(&mut state, Key::ArrowDown, false);
+        assert_eq!(
+            (state.cursor.group_idx, state.cursor.item_idx),
+            (0, Some(0))
+        );
+        gs.handle_key(&mut state, Key::Char(' '), false);
+        assert_eq!(state.checked, vec![vec![true, false], vec![true]]);
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        assert_eq!(render.into_lines()[2], ">   ■ a1");
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_run_with_stream() {
+        let gs = GroupMultiSelect::new()
             .group("A", vec!["a1", "a2"])
             .group("B", vec!["b1"]);
+        let keys = futures::stream::iter(vec![
+            Key::ArrowDown,
+            Key::Char(' '),
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::ArrowDown,
+            Key::Char(' '),
+            Key::Enter,
+        ]);
+        let selection = futures::executor::block_on(gs.run_with_stream(keys)).unwrap();
+        assert_eq!(selection, Some(vec![vec![0], vec![0]]));
 
-        for flat in 0..gs.total_rows() {
-            let cursor = gs.flat_to_cursor(flat);
-            assert_eq!(gs.cursor_to_flat(cursor), flat);
-        }
+        let gs = GroupMultiSelect::new().group("A", vec!["a1"]);
+        let keys = futures::stream::iter(vec![Key::Char(' ')]);
+        assert!(futures::executor::block_on(gs.run_with_stream(keys)).is_err());
+        // Each key waits for the recompute started by the previous one, so the cursor
+        // skips the item disabled by checking a1
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2", "a3"])
+            .recompute_states_in_background(|checked| {
+                thread::sleep(Duration::from_millis(20));
+                let state = if checked[0][0] {
+                    ItemState::Disabled {
+                        reason: "off".to_string(),
+                    }
+                } else {
+                    ItemState::Normal
+                };
+                vec![vec![ItemState::Normal, state, ItemState::Normal]]
+            });
+        let keys = futures::stream::iter(vec![
+            Key::ArrowDown,
+            Key::Char(' '),
+            Key::ArrowDown,
+            Key::Char(' '),
+            Key::Enter,
+        ]);
+        let selection = futures::executor::block_on(gs.run_with_stream(keys)).unwrap();
+        assert_eq!(selection, Some(vec![vec![0, 2]]));
     }
 
     #[test]
-    fn test_group_state() {
-        assert!(matches!(
-            GroupMultiSelect::<&str>::group_state(&[]),
-            GroupState::None
-        ));
-        assert!(matches!(
-            GroupMultiSelect::<&str>::group_state(&[false, false]),
-            GroupState::None
-        ));
-        assert!(matches!(
-            GroupMultiSelect::<&str>::group_state(&[true, false]),
-            GroupState::Partial
-        ));
-        assert!(matches!(
-            GroupMultiSelect::<&str>::group_state(&[true, true]),
-            GroupState::All
-        ));
+    fn test_selection_round_trip() {
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .quit_returns_progress(true);
+        let mut component = gs.component(TEST_TERM_SIZE).unwrap();
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::ArrowDown);
+        component.handle_key(Key::Char(' '));
+        assert_eq!(component.handle_key(Key::Escape), ComponentEvent::Cancelled);
+        let selection = component.outcome().to_selection();
+        assert_eq!(
+            selection,
+            Selection::new(vec![vec![false, true], vec![false]])
+        );
+
+        let gs = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .with_selection(selection);
+        assert_eq!(gs.initial_checked(), vec![vec![false, true], vec![false]]);
     }
 
     #[test]
-    fn test_toggle_group() {
-        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new()
+    fn test_with_selection_mismatched_dimensions() {
+        let gs = GroupMultiSelect::new()
             .group("A", vec!["a1", "a2"])
-            .group("B", vec!["b1"]);
+            .group("B", vec!["b1"])
+            .with_selection(Selection::new(vec![
+                vec![true, true, true],
+                vec![],
+                vec![true],
+            ]));
+        assert_eq!(gs.initial_checked(), vec![vec![true, true], vec![false]]);
+        assert_eq!(
+            gs.interact_with_keys([]).unwrap(),
+            Some(vec![vec![0, 1], vec![]])
+        );
+    }
 
-        let mut checked = vec![vec![false, false], vec![false]];
-        let cursor = Cursor {
-            group_idx: 0,
-            item_idx: None,
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_selection_serde() {
+        let selection = Selection::new(vec![vec![true, false], vec![], vec![false]]);
+        let json = serde_json::to_string(&selection).unwrap();
+        assert_eq!(json, r#"{"checked":[[true,false],[],[false]]}"#);
+        assert_eq!(serde_json::from_str::<Selection>(&json).unwrap(), selection);
+    }
+
+    #[test]
+    fn test_on_toggle() {
+        let mut events = Vec::new();
+        GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .group("B", vec!["b1"])
+            .defaults(vec![vec![true, false]])
+            .on_toggle(|event| events.push(*event))
+            .interact_with_keys([
+                Key::ArrowDown,
+                Key::ArrowDown,
+                Key::Char(' '),
+                Key::ArrowUp,
+                Key::ArrowUp,
+                Key::Char(' '),
+            ])
+            .unwrap();
+        let event = |group_idx, item_idx, now_checked| ToggleEvent {
+            group_idx,
+            item_idx: Some(item_idx),
+            now_checked,
         };
+        assert_eq!(
+            events,
+            vec![event(0, 1, true), event(0, 0, false), event(0, 1, false)]
+        );
+    }
 
-        gs.toggle(&mut checked, cursor);
-        assert_eq!(checked[0], vec![true, true]);
+    #[test]
+    fn test_show_group_counts() {
+        let gs = GroupMultiSelect::new()
+            .group_with_states(
+                "A",
+                vec![
+                    ("a1", ItemState::Normal),
+                    ("a2", ItemState::Normal),
+                    (
+                        "a3",
+                        ItemState::Disabled {
+                            reason: "gone".to_string(),
+                        },
+                    ),
+                ],
+            )
+            .defaults(vec![vec![true, false, true]]);
+        let state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
 
-        gs.toggle(&mut checked, cursor);
-        assert_eq!(checked[0], vec![false, false]);
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        assert_eq!(render.into_lines()[1], "> ◐ A (1/2)");
+
+        let gs = gs.show_group_counts(false);
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        assert_eq!(render.into_lines()[1], "> ◐ A");
     }
 
     #[test]
-    fn test_toggle_item() {
-        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group("A", vec!["a1", "a2"]);
+    fn test_format_item() {
+        struct Package {
+            name: &'static str,
+            size: usize,
+        }
 
-        let mut checked = vec![vec![false, false]];
-        let cursor = Cursor {
-            group_idx: 0,
-            item_idx: Some(1),
-        };
+        impl std::fmt::Display for Package {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name)
+            }
+        }
 
-        gs.toggle(&mut checked, cursor);
-        assert_eq!(checked[0], vec![false, true]);
+        let package = |name, size| Package { name, size };
+        let gs = GroupMultiSelect::new()
+            .group("A", vec![package("a1", 12), package("a2", 3)])
+            .defaults(vec![vec![true, false]])
+            .format_item(|&Package { name, size }, checked, active| {
+                format!(
+                    "{} — {}MB{}{}",
+                    name,
+                    size,
+                    if checked { " *" } else { "" },
+                    if active { " <" } else { "" }
+                )
+            });
+        let mut state = gs.new_state(gs.initial_checked(), TEST_TERM_SIZE);
+        state.cursor = gs.move_cursor_down(&state.view, state.cursor);
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render(&mut render, &state).unwrap();
+        let lines = render.into_lines();
+        assert_eq!(lines[2], ">   ☑ a1 — 12MB * <");
+        assert_eq!(lines[3], "    ☐ a2 — 3MB");
+
+        let mut render = TermThemeRenderer::buffered(&SimpleTheme);
+        gs.render_report(&mut render, &state.checked, 80).unwrap();
+        assert!(render.into_lines().concat().contains("a1 — 12MB *"));
     }
 
     #[test]
-    fn test_disabled_item_cannot_toggle() {
-        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group_with_states(
-            "A",
-            vec![
-                ("a1", ItemState::Normal),
-                (
-                    "a2",
-                    ItemState::Disabled {
-                        reason: "test".into(),
-                    },
-                ),
-            ],
+    fn test_scroll_indicators() {
+        let items: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", items.clone())
+                .max_length(5)
+        };
+        let frame =
+            |component: &GroupMultiSelectComponent<'_, String>| component.frame().unwrap().lines;
+
+        let mut component = gs().component(TEST_TERM_SIZE).unwrap();
+        let lines = frame(&component);
+        assert_eq!(lines.len(), 1 + 4);
+        assert_eq!(lines[1..4], ["> ○ A (0/10)", "    ☐ 0", "    ☐ 1"]);
+        assert_eq!(lines[4], "  ↓ more");
+
+        for _ in 0..5 {
+            component.handle_key(Key::ArrowDown);
+        }
+        let lines = frame(&component);
+        assert_eq!(lines.len(), 1 + 5);
+        assert_eq!(lines[1], "  ↑ more");
+        assert_eq!(lines[2..5], ["    ☐ 2", "    ☐ 3", ">   ☐ 4"]);
+        assert_eq!(lines[5], "  ↓ more");
+
+        let plain = frame(
+            &gs()
+                .show_scroll_indicators(false)
+                .component(TEST_TERM_SIZE)
+                .unwrap(),
         );
+        assert_eq!(plain.len(), 1 + 5);
+        assert!(plain.iter().all(|line| !line.contains("more")));
+    }
 
-        let mut checked = vec![vec![false, false]];
-        let cursor = Cursor {
-            group_idx: 0,
-            item_idx: Some(1),
-        };
+    #[cfg(all(unix, feature = "async"))]
+    #[test]
+    fn test_interact_async_report() {
+        let path = std::env::temp_dir().join(format!(
+            "dialoguer-multiselect-async-report-{}",
+            std::process::id()
+        ));
+        let out = std::fs::File::create(&path).unwrap();
+        let term = Term::read_write_pair(std::fs::File::open("/dev/null").unwrap(), out);
 
-        gs.toggle(&mut checked, cursor);
-        assert_eq!(checked[0], vec![false, false]);
+        let gs = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group("A", vec!["a1", "a2"])
+            .report_counts(true);
+        let keys = futures::stream::iter(vec![Key::ArrowDown, Key::Char(' '), Key::Enter]);
+        let selection = futures::executor::block_on(gs.interact_async_on(&term, keys)).unwrap();
+        assert_eq!(selection, Some(vec![vec![0]]));
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written.contains("Selected 1 of 2 items across 1 groups."));
     }
 
     #[test]
-    fn test_group_toggle_skips_disabled() {
-        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group_with_states(
-            "A",
-            vec![
-                ("a1", ItemState::Normal),
-                (
-                    "a2",
-                    ItemState::Disabled {
-                        reason: "test".into(),
-                    },
-                ),
-                ("a3", ItemState::Normal),
-            ],
-        );
+    fn test_recompute_skips_superseded_jobs() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let mut component = GroupMultiSelect::new()
+            .group("A", vec!["a1", "a2"])
+            .recompute_states_in_background(move |checked| {
+                recorded.lock().unwrap().push(checked.to_vec());
+                thread::sleep(Duration::from_millis(50));
+                vec![vec![ItemState::Normal; 2]]
+            })
+            .component(TEST_TERM_SIZE)
+            .unwrap();
 
-        let mut checked = vec![vec![false, false, false]];
-        let cursor = Cursor {
-            group_idx: 0,
-            item_idx: None,
-        };
+        component.handle_key(Key::ArrowDown);
+        for _ in 0..3 {
+            component.handle_key(Key::Char(' '));
+        }
+        while component.state.recompute.is_some() {
+            component.poll();
+            thread::sleep(RECOMPUTE_POLL_INTERVAL);
+        }
 
-        gs.toggle(&mut checked, cursor);
-        assert_eq!(checked[0], vec![true, false, true]);
+        let seen = seen.lock().unwrap();
+        assert!(seen.len() <= 2);
+        assert_eq!(seen.last(), Some(&vec![vec![true, false]]));
     }
 
     #[test]
-    fn test_cursor_skips_disabled() {
-        let gs: GroupMultiSelect<'_, &str> = GroupMultiSelect::new().group_with_states(
-            "A",
-            vec![
-                ("a1", ItemState::Normal),
-                (
-                    "a2",
-                    ItemState::Disabled {
-                        reason: "test".into(),
-                    },
-                ),
-                ("a3", ItemState::Normal),
-            ],
+    fn test_review_item_states() {
+        let mut component = GroupMultiSelect::new()
+            .with_prompt("Pick")
+            .group_with_states(
+                "A",
+                vec![("a1", ItemState::Locked), ("a2", ItemState::Normal)],
+            )
+            .defaults(vec![vec![false, true]])
+            .review_step(true)
+            .component(TEST_TERM_SIZE)
+            .unwrap();
+
+        component.handle_key(Key::Enter);
+        assert_eq!(
+            component.frame().unwrap().lines,
+            vec!["Pick:", ">   ■ a1", "    ☑ a2"]
         );
+    }
 
-        let cursor = Cursor {
-            group_idx: 0,
-            item_idx: Some(0),
+    #[cfg(unix)]
+    #[test]
+    fn test_action_key_interrupts() {
+        struct Script(std::vec::IntoIter<Key>);
+
+        impl KeySource for Script {
+            fn read_key(&mut self) -> io::Result<Key> {
+                self.0
+                    .next()
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+            }
+        }
+
+        let null = || {
+            std::fs::File::options()
+                .write(true)
+                .open("/dev/null")
+                .unwrap()
+        };
+        let term = Term::read_write_pair(null(), null());
+        let gs = || {
+            GroupMultiSelect::new()
+                .group("A", vec!["a1"])
+                .action_key(Key::Char('+'))
         };
-        let new_cursor = gs.move_cursor_down(cursor);
-        assert_eq!(new_cursor.item_idx, Some(2));
 
-        let back_cursor = gs.move_cursor_up(new_cursor);
-        assert_eq!(back_cursor.item_idx, Some(0));
+        let keys = Script(vec![Key::Char('+')].into_iter());
+        let err = io::Error::from(gs().interact_on_with(&term, keys).unwrap_err());
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        let keys = Script(vec![Key::Char('+')].into_iter());
+        assert_eq!(gs().interact_on_opt_with(&term, keys).unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_toggle_flash_reads_without_timeout() {
+        struct Script(std::vec::IntoIter<Key>);
+
+        impl KeySource for Script {
+            fn read_key(&mut self) -> io::Result<Key> {
+                self.0
+                    .next()
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+            }
+
+            fn read_key_timeout(&mut self, _: Duration) -> io::Result<Option<Key>> {
+                panic!("timed read without a background recompute");
+            }
+        }
+
+        let null = || {
+            std::fs::File::options()
+                .write(true)
+                .open("/dev/null")
+                .unwrap()
+        };
+        let term = Term::read_write_pair(null(), null());
+        let keys = Script(vec![Key::ArrowDown, Key::Char(' '), Key::Enter].into_iter());
+        let selection = GroupMultiSelect::new()
+            .group("A", vec!["a1"])
+            .toggle_flash(true)
+            .interact_on_with(&term, keys)
+            .unwrap();
+        assert_eq!(selection, vec![vec![0]]);
     }
 }
@@ -24,7 +24,7 @@ type InputValidatorCallback<'a, T> = Arc<Mutex<dyn FnMut(&T) -> Option<String> +
 /// ## Example
 ///
 /// ```rust,no_run
-/// use dialoguer::Input;
+/// use dialoguer_multiselect::Input;
 ///
 /// fn main() {
 ///     let name: String = Input::new()
@@ -39,7 +39,7 @@ type InputValidatorCallback<'a, T> = Arc<Mutex<dyn FnMut(&T) -> Option<String> +
 /// It can also be used with turbofish notation:
 ///
 /// ```rust,no_run
-/// use dialoguer::Input;
+/// use dialoguer_multiselect::Input;
 ///
 /// fn main() {
 ///     let name = Input::<String>::new()
@@ -141,7 +141,7 @@ impl<'a, T> Input<'a, T> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::{theme::ColorfulTheme, Input};
+    /// use dialoguer_multiselect::{theme::ColorfulTheme, Input};
     ///
     /// fn main() {
     ///     let name: String = Input::with_theme(&ColorfulTheme::default())
@@ -173,7 +173,7 @@ impl<'a, T> Input<'a, T> {
     ///
     /// ```rust,no_run
     /// use std::{collections::VecDeque, fmt::Display};
-    /// use dialoguer::{History, Input};
+    /// use dialoguer_multiselect::{History, Input};
     ///
     /// struct MyHistory {
     ///     history: VecDeque<String>,
@@ -237,7 +237,7 @@ where
     /// # Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::Input;
+    /// use dialoguer_multiselect::Input;
     ///
     /// fn main() {
     ///     let mail: String = Input::new()
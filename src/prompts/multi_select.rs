@@ -12,7 +12,7 @@ use crate::{
 /// ## Example
 ///
 /// ```rust,no_run
-/// use dialoguer::MultiSelect;
+/// use dialoguer_multiselect::MultiSelect;
 ///
 /// fn main() {
 ///     let items = vec!["foo", "bar", "baz"];
@@ -159,7 +159,7 @@ impl MultiSelect<'_> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::MultiSelect;
+    /// use dialoguer_multiselect::MultiSelect;
     ///
     /// fn main() {
     ///     let items = vec!["foo", "bar", "baz"];
@@ -266,15 +266,11 @@ impl MultiSelect<'_> {
                             % (self.items.len() as i64)) as usize;
                     }
                 }
-                Key::ArrowLeft | Key::Char('h') => {
-                    if paging.active {
-                        sel = paging.previous_page();
-                    }
+                Key::ArrowLeft | Key::Char('h') if paging.active => {
+                    sel = paging.previous_page();
                 }
-                Key::ArrowRight | Key::Char('l') => {
-                    if paging.active {
-                        sel = paging.next_page();
-                    }
+                Key::ArrowRight | Key::Char('l') if paging.active => {
+                    sel = paging.next_page();
                 }
                 Key::Char(' ') => {
                     checked[sel] = !checked[sel];
@@ -286,19 +282,17 @@ impl MultiSelect<'_> {
                         checked.fill(true);
                     }
                 }
-                Key::Escape | Key::Char('q') => {
-                    if allow_quit {
-                        if self.clear {
-                            render.clear()?;
-                        } else {
-                            term.clear_last_lines(paging.capacity)?;
-                        }
+                Key::Escape | Key::Char('q') if allow_quit => {
+                    if self.clear {
+                        render.clear()?;
+                    } else {
+                        term.clear_last_lines(paging.capacity)?;
+                    }
 
-                        term.show_cursor()?;
-                        term.flush()?;
+                    term.show_cursor()?;
+                    term.flush()?;
 
-                        return Ok(None);
-                    }
+                    return Ok(None);
                 }
                 Key::Enter => {
                     if self.clear {
@@ -354,7 +348,7 @@ impl<'a> MultiSelect<'a> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::{theme::ColorfulTheme, MultiSelect};
+    /// use dialoguer_multiselect::{theme::ColorfulTheme, MultiSelect};
     ///
     /// fn main() {
     ///     let selection = MultiSelect::with_theme(&ColorfulTheme::default())
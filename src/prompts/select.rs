@@ -15,7 +15,7 @@ use crate::{
 /// ## Example
 ///
 /// ```rust,no_run
-/// use dialoguer::Select;
+/// use dialoguer_multiselect::Select;
 ///
 /// fn main() {
 ///     let items = vec!["foo", "bar", "baz"];
@@ -87,7 +87,7 @@ impl Select<'_> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::Select;
+    /// use dialoguer_multiselect::Select;
     ///
     /// fn main() {
     ///     let selection = Select::new()
@@ -153,7 +153,7 @@ impl Select<'_> {
     /// ## Example
     ///
     ///```rust,no_run
-    /// use dialoguer::Select;
+    /// use dialoguer_multiselect::Select;
     ///
     /// fn main() {
     ///     let items = vec!["foo", "bar", "baz"];
@@ -246,19 +246,17 @@ impl Select<'_> {
                         sel = (sel as u64 + 1).rem(self.items.len() as u64) as usize;
                     }
                 }
-                Key::Escape | Key::Char('q') => {
-                    if allow_quit {
-                        if self.clear {
-                            render.clear()?;
-                        } else {
-                            term.clear_last_lines(paging.capacity)?;
-                        }
+                Key::Escape | Key::Char('q') if allow_quit => {
+                    if self.clear {
+                        render.clear()?;
+                    } else {
+                        term.clear_last_lines(paging.capacity)?;
+                    }
 
-                        term.show_cursor()?;
-                        term.flush()?;
+                    term.show_cursor()?;
+                    term.flush()?;
 
-                        return Ok(None);
-                    }
+                    return Ok(None);
                 }
                 Key::ArrowUp | Key::BackTab | Key::Char('k') => {
                     if sel == !0 {
@@ -268,15 +266,11 @@ impl Select<'_> {
                             % (self.items.len() as i64)) as usize;
                     }
                 }
-                Key::ArrowLeft | Key::Char('h') => {
-                    if paging.active {
-                        sel = paging.previous_page();
-                    }
+                Key::ArrowLeft | Key::Char('h') if paging.active => {
+                    sel = paging.previous_page();
                 }
-                Key::ArrowRight | Key::Char('l') => {
-                    if paging.active {
-                        sel = paging.next_page();
-                    }
+                Key::ArrowRight | Key::Char('l') if paging.active => {
+                    sel = paging.next_page();
                 }
 
                 Key::Enter | Key::Char(' ') if sel != !0 => {
@@ -315,7 +309,7 @@ impl<'a> Select<'a> {
     /// ## Example
     ///
     /// ```rust,no_run
-    /// use dialoguer::{theme::ColorfulTheme, Select};
+    /// use dialoguer_multiselect::{theme::ColorfulTheme, Select};
     ///
     /// fn main() {
     ///     let selection = Select::with_theme(&ColorfulTheme::default())
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use console::{style, Style, StyledObject};
+use console::{measure_text_width, style, Color, Style, StyledObject};
 #[cfg(feature = "fuzzy-select")]
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
@@ -56,6 +56,10 @@ pub struct ColorfulTheme {
     pub group_partial_prefix: StyledObject<String>,
     /// Group header prefix when no items selected
     pub group_none_prefix: StyledObject<String>,
+    /// Marker for group items changed from their default
+    pub changed_item_marker: StyledObject<String>,
+    /// The style for the header of the group containing the cursor
+    pub active_group_style: Style,
 }
 
 impl Default for ColorfulTheme {
@@ -86,6 +90,8 @@ impl Default for ColorfulTheme {
             group_all_prefix: style("◉".to_string()).for_stderr().green(),
             group_partial_prefix: style("◐".to_string()).for_stderr().yellow(),
             group_none_prefix: style("○".to_string()).for_stderr().white().dim(),
+            changed_item_marker: style("*".to_string()).for_stderr().yellow(),
+            active_group_style: Style::new().for_stderr().bold(),
         }
     }
 }
@@ -434,6 +440,40 @@ impl Theme for ColorfulTheme {
         write!(f, "{} {} {}", cursor, prefix, styled_text)
     }
 
+    fn format_group_multi_select_disabled_header(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        reason: &str,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.inactive_item_prefix,
+            self.hint_style.apply_to(format!("⊘ {} ({})", text, reason))
+        )
+    }
+
+    fn format_group_multi_select_active_group_header(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        state: GroupState,
+    ) -> fmt::Result {
+        let prefix = match state {
+            GroupState::All => &self.group_all_prefix,
+            GroupState::Partial => &self.group_partial_prefix,
+            GroupState::None => &self.group_none_prefix,
+        };
+        write!(
+            f,
+            "{} {} {}",
+            &self.inactive_item_prefix,
+            prefix,
+            self.active_group_style.apply_to(text)
+        )
+    }
+
     fn format_group_multi_select_item(
         &self,
         f: &mut dyn fmt::Write,
@@ -459,6 +499,31 @@ impl Theme for ColorfulTheme {
         write!(f, "{}   {} {}", cursor, check_prefix, styled_text)
     }
 
+    fn format_group_multi_select_item_locked(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        active: bool,
+    ) -> fmt::Result {
+        let cursor = if active {
+            &self.active_item_prefix
+        } else {
+            &self.inactive_item_prefix
+        };
+        let styled_text = if active {
+            self.active_item_style.apply_to(text)
+        } else {
+            self.inactive_item_style.apply_to(text)
+        };
+        write!(
+            f,
+            "{}   {} {}",
+            cursor,
+            self.hint_style.apply_to("■"),
+            styled_text
+        )
+    }
+
     fn format_group_multi_select_item_warning(
         &self,
         f: &mut dyn fmt::Write,
@@ -492,6 +557,226 @@ impl Theme for ColorfulTheme {
         )
     }
 
+    fn format_group_multi_select_accent(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        accent: Color,
+    ) -> fmt::Result {
+        write!(f, "{}", Style::new().for_stderr().fg(accent).apply_to(text))
+    }
+
+    fn format_group_multi_select_query_match(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            Style::new().for_stderr().underlined().apply_to(text)
+        )
+    }
+
+    fn format_group_multi_select_quick_key(
+        &self,
+        f: &mut dyn fmt::Write,
+        key: char,
+    ) -> fmt::Result {
+        write!(f, "{} ", self.hint_style.apply_to(format!("[{}]", key)))
+    }
+
+    fn format_group_multi_select_flash_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " {}", style("•").for_stderr().bold().yellow())
+    }
+
+    fn format_group_multi_select_changed_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " {}", &self.changed_item_marker)
+    }
+
+    fn format_group_multi_select_item_level(
+        &self,
+        f: &mut dyn fmt::Write,
+        label: &str,
+    ) -> fmt::Result {
+        write!(f, " {}", self.values_style.apply_to(label))
+    }
+
+    fn format_group_multi_select_subtitle(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+    ) -> fmt::Result {
+        write!(f, "    {}", self.hint_style.apply_to(text))
+    }
+
+    fn format_group_multi_select_collapsed_count(
+        &self,
+        f: &mut dyn fmt::Write,
+        checked: usize,
+        total: usize,
+    ) -> fmt::Result {
+        write!(
+            f,
+            " {}",
+            self.hint_style.apply_to(format!("({}/{})", checked, total))
+        )
+    }
+
+    fn format_group_multi_select_collapsed_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " {}", self.hint_style.apply_to("…"))
+    }
+
+    fn format_group_multi_select_report_continuation(
+        &self,
+        f: &mut dyn fmt::Write,
+        selections: &[&str],
+    ) -> fmt::Result {
+        for (idx, sel) in selections.iter().enumerate() {
+            write!(
+                f,
+                "{}{}",
+                if idx == 0 { "" } else { ", " },
+                self.values_style.apply_to(sel)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn format_group_multi_select_report_counts(
+        &self,
+        f: &mut dyn fmt::Write,
+        selected: usize,
+        total: usize,
+        groups: usize,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.hint_style.apply_to(format!(
+                "Selected {} of {} items across {} groups.",
+                selected, total, groups
+            ))
+        )
+    }
+
+    fn format_group_multi_select_violation_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " {}", &self.error_prefix)
+    }
+
+    fn format_group_multi_select_quit_report(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        message: &str,
+    ) -> fmt::Result {
+        write!(f, "{} ", &self.error_prefix)?;
+        if !prompt.is_empty() {
+            write!(f, "{} ", self.prompt_style.apply_to(prompt))?;
+        }
+        write!(f, "{}", self.error_style.apply_to(message))
+    }
+
+    fn format_group_multi_select_tooltip(&self, f: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+        write!(f, "{}", self.hint_style.apply_to(text))
+    }
+
+    fn format_group_multi_select_scroll_indicator(
+        &self,
+        f: &mut dyn fmt::Write,
+        above: bool,
+    ) -> fmt::Result {
+        let arrow = if above { "↑" } else { "↓" };
+        write!(
+            f,
+            "  {}",
+            self.hint_style.apply_to(format!("{} more", arrow))
+        )
+    }
+
+    fn format_group_multi_select_help(&self, f: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+        write!(f, "{}", self.hint_style.apply_to(text))
+    }
+
+    fn format_group_multi_select_status(
+        &self,
+        f: &mut dyn fmt::Write,
+        status: &str,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            &self.error_prefix,
+            self.error_style.apply_to(status)
+        )
+    }
+
+    fn format_group_multi_select_search_query(
+        &self,
+        f: &mut dyn fmt::Write,
+        query: &str,
+        bytes_pos: usize,
+    ) -> fmt::Result {
+        let (head, remaining) = query.split_at(bytes_pos);
+        let mut chars = remaining.chars();
+        let cursor = Style::new()
+            .for_stderr()
+            .reverse()
+            .apply_to(chars.next().unwrap_or(' '));
+        write!(
+            f,
+            " {}{head}{cursor}{}",
+            self.hint_style.apply_to("/"),
+            chars.as_str()
+        )
+    }
+
+    fn format_group_multi_select_count(
+        &self,
+        f: &mut dyn fmt::Write,
+        selected: usize,
+        max: Option<usize>,
+    ) -> fmt::Result {
+        let count = match max {
+            Some(max) => format!("{}/{} selected", selected, max),
+            None => format!("{} selected", selected),
+        };
+        write!(f, " {}", self.hint_style.apply_to(count))
+    }
+
+    fn format_group_multi_select_busy_indicator(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " {}", self.hint_style.apply_to("recomputing…"))
+    }
+
+    fn format_group_multi_select_box_top(
+        &self,
+        f: &mut dyn fmt::Write,
+        width: usize,
+    ) -> fmt::Result {
+        let border = format!("┌{}┐", "─".repeat(width + 2));
+        write!(f, "{}", self.hint_style.apply_to(border))
+    }
+
+    fn format_group_multi_select_box_bottom(
+        &self,
+        f: &mut dyn fmt::Write,
+        width: usize,
+    ) -> fmt::Result {
+        let border = format!("└{}┘", "─".repeat(width + 2));
+        write!(f, "{}", self.hint_style.apply_to(border))
+    }
+
+    fn format_group_multi_select_box_row(
+        &self,
+        f: &mut dyn fmt::Write,
+        row: &str,
+        width: usize,
+    ) -> fmt::Result {
+        let padding = width.saturating_sub(measure_text_width(row));
+        let side = self.hint_style.apply_to("│");
+        write!(f, "{} {}{} {}", side, row, " ".repeat(padding), side)
+    }
+
     /// Formats a fuzzy-selectprompt after selection.
     #[cfg(feature = "fuzzy-select")]
     fn format_fuzzy_select_prompt(
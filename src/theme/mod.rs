@@ -3,6 +3,7 @@ use std::fmt;
 
 #[cfg(feature = "fuzzy-select")]
 use console::style;
+use console::{measure_text_width, Color};
 #[cfg(feature = "fuzzy-select")]
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
@@ -13,6 +14,56 @@ mod simple;
 pub use colorful::ColorfulTheme;
 pub use simple::SimpleTheme;
 
+use crate::ItemState;
+
+/// Everything known about a single GroupMultiSelect row.
+///
+/// Passed to [`Theme::format_group_multi_select_row`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct RowContext<'a> {
+    /// Index of the group the row belongs to
+    pub group_idx: usize,
+    /// Index of the item within its group, `None` for group headers
+    pub item_idx: Option<usize>,
+    /// Group label or item text
+    pub text: &'a str,
+    /// State of the item, `None` for group headers
+    pub item_state: Option<&'a ItemState>,
+    /// Checkbox state of the group the row belongs to
+    pub group_state: GroupState,
+    /// Whether the item is checked, `false` for group headers
+    pub checked: bool,
+    /// Whether the cursor is on this row
+    pub active: bool,
+    /// Whether the cursor is in the group of this row and its highlighting is enabled
+    pub in_active_group: bool,
+    /// Whether the group is collapsed
+    pub collapsed: bool,
+    /// Number of checked items and number of items in the group
+    pub counts: (usize, usize),
+    /// Whether the counts are shown in the header of an expanded group
+    pub show_counts: bool,
+    /// Whether the selection of the group currently meets its constraints
+    pub constraint_ok: bool,
+    /// Whether the item was toggled a moment ago and should be highlighted
+    pub recently_changed: bool,
+    /// Whether the item was changed from its default and changes are marked
+    pub changed: bool,
+    /// Label of the current state of an item that cycles through states
+    pub level: Option<&'a str>,
+    /// Accent color of the group the row belongs to
+    pub accent: Option<Color>,
+    /// Byte range of the group label matched by the search query, `None` for items
+    pub label_match: Option<(usize, usize)>,
+    /// Key that toggles the item with quick keys enabled
+    pub quick_key: Option<char>,
+    /// Why the group the row belongs to is unavailable, `None` for available groups
+    pub disabled_reason: Option<&'a str>,
+    /// Position of the row on the current page, starting at 0
+    pub row: usize,
+}
+
 /// Represents the selection state of a group in GroupMultiSelect.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GroupState {
@@ -291,6 +342,115 @@ pub trait Theme {
         write!(f, "{} {} {}", prefix, icon, text)
     }
 
+    /// Formats the header of a GroupMultiSelect group that is unavailable.
+    #[inline]
+    fn format_group_multi_select_disabled_header(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        reason: &str,
+    ) -> fmt::Result {
+        write!(f, "  \x1b[90m⊘ {} ({})\x1b[0m", text, reason)
+    }
+
+    /// Formats any GroupMultiSelect row.
+    ///
+    /// The default implementation dispatches to the hooks for the individual row types,
+    /// so overriding this allows rendering all rows uniformly, e.g. with zebra striping.
+    fn format_group_multi_select_row(
+        &self,
+        f: &mut dyn fmt::Write,
+        row: &RowContext<'_>,
+    ) -> fmt::Result {
+        if let Some(key) = row.quick_key {
+            self.format_group_multi_select_quick_key(f, key)?;
+        }
+        let mut matched = String::new();
+        if let Some((start, end)) = row.label_match {
+            matched.push_str(&row.text[..start]);
+            self.format_group_multi_select_query_match(&mut matched, &row.text[start..end])?;
+            matched.push_str(&row.text[end..]);
+        }
+        let text = if row.label_match.is_some() {
+            &matched
+        } else {
+            row.text
+        };
+        let mut accented = String::new();
+        let text = match row.accent {
+            Some(accent) if row.item_idx.is_none() || row.active => {
+                self.format_group_multi_select_accent(&mut accented, text, accent)?;
+                &accented
+            }
+            _ => text,
+        };
+        match row.item_state {
+            None => {
+                if let Some(reason) = row.disabled_reason {
+                    return self.format_group_multi_select_disabled_header(f, text, reason);
+                }
+                if row.in_active_group && !row.active {
+                    self.format_group_multi_select_active_group_header(f, text, row.group_state)?;
+                } else {
+                    self.format_group_multi_select_header(f, text, row.group_state, row.active)?;
+                }
+                if row.collapsed || row.show_counts {
+                    self.format_group_multi_select_collapsed_count(f, row.counts.0, row.counts.1)?;
+                }
+                if row.collapsed {
+                    self.format_group_multi_select_collapsed_marker(f)?;
+                }
+                if !row.constraint_ok {
+                    self.format_group_multi_select_violation_marker(f)?;
+                }
+                return Ok(());
+            }
+            Some(ItemState::Disabled { reason }) => {
+                return self.format_group_multi_select_item_disabled(f, text, reason, row.active);
+            }
+            Some(ItemState::Warning { message }) => {
+                self.format_group_multi_select_item_warning(
+                    f,
+                    text,
+                    message,
+                    row.checked,
+                    row.active,
+                )?;
+            }
+            Some(ItemState::Locked) => {
+                self.format_group_multi_select_item_locked(f, text, row.active)?;
+            }
+            Some(_) => {
+                self.format_group_multi_select_item(f, text, row.checked, row.active)?;
+            }
+        }
+        if let Some(level) = row.level {
+            self.format_group_multi_select_item_level(f, level)?;
+        }
+        if row.changed {
+            self.format_group_multi_select_changed_marker(f)?;
+        }
+        if row.recently_changed {
+            self.format_group_multi_select_flash_marker(f)?;
+        }
+        Ok(())
+    }
+
+    /// Formats the header of the group containing the cursor while an item is focused.
+    fn format_group_multi_select_active_group_header(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        state: GroupState,
+    ) -> fmt::Result {
+        let icon = match state {
+            GroupState::All => "◉",
+            GroupState::Partial => "◐",
+            GroupState::None => "○",
+        };
+        write!(f, "· {} {}", icon, text)
+    }
+
     /// Formats a group item row (indented) in GroupMultiSelect.
     fn format_group_multi_select_item(
         &self,
@@ -304,6 +464,18 @@ pub trait Theme {
         write!(f, "{}   {} {}", prefix, icon, text)
     }
 
+    /// Formats a GroupMultiSelect item that is always selected.
+    #[inline]
+    fn format_group_multi_select_item_locked(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        active: bool,
+    ) -> fmt::Result {
+        let prefix = if active { ">" } else { " " };
+        write!(f, "{}   ■ {}", prefix, text)
+    }
+
     /// Formats a group multi select prompt.
     #[inline]
     fn format_group_multi_select_prompt(
@@ -324,6 +496,46 @@ pub trait Theme {
         self.format_multi_select_prompt_selection(f, prompt, selections)
     }
 
+    /// Formats the items of a GroupMultiSelect report that continue on another line.
+    #[inline]
+    fn format_group_multi_select_report_continuation(
+        &self,
+        f: &mut dyn fmt::Write,
+        selections: &[&str],
+    ) -> fmt::Result {
+        write!(f, "{}", selections.join(", "))
+    }
+
+    /// Formats the selection counts above a GroupMultiSelect report.
+    #[inline]
+    fn format_group_multi_select_report_counts(
+        &self,
+        f: &mut dyn fmt::Write,
+        selected: usize,
+        total: usize,
+        groups: usize,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "Selected {} of {} items across {} groups.",
+            selected, total, groups
+        )
+    }
+
+    /// Formats the line reported when a GroupMultiSelect prompt is quit.
+    #[inline]
+    fn format_group_multi_select_quit_report(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        message: &str,
+    ) -> fmt::Result {
+        if !prompt.is_empty() {
+            write!(f, "{}: ", prompt)?;
+        }
+        write!(f, "{}", message)
+    }
+
     fn format_group_multi_select_item_disabled(
         &self,
         f: &mut dyn fmt::Write,
@@ -351,4 +563,208 @@ pub trait Theme {
             prefix, icon, text, message
         )
     }
+
+    /// Formats the marker appended to a group item whose checked state differs from its default.
+    #[inline]
+    fn format_group_multi_select_changed_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " *")
+    }
+
+    /// Formats the label of the current state of an item that cycles through states.
+    #[inline]
+    fn format_group_multi_select_item_level(
+        &self,
+        f: &mut dyn fmt::Write,
+        label: &str,
+    ) -> fmt::Result {
+        write!(f, " [{}]", label)
+    }
+
+    /// Formats a line of the prologue above a GroupMultiSelect group header.
+    #[inline]
+    fn format_group_multi_select_prologue(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+    ) -> fmt::Result {
+        write!(f, "{}", text)
+    }
+
+    /// Formats the subtitle line below a GroupMultiSelect group header.
+    #[inline]
+    fn format_group_multi_select_subtitle(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+    ) -> fmt::Result {
+        write!(f, "    {}", text)
+    }
+
+    /// Formats a GroupMultiSelect header label or highlighted item in the accent color of its group.
+    #[inline]
+    fn format_group_multi_select_accent(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        accent: Color,
+    ) -> fmt::Result {
+        let _ = accent;
+        write!(f, "{}", text)
+    }
+
+    /// Formats the part of a GroupMultiSelect group label matched by the search query.
+    #[inline]
+    fn format_group_multi_select_query_match(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+    ) -> fmt::Result {
+        write!(f, "{}", text)
+    }
+
+    /// Formats the hint key in front of a GroupMultiSelect item.
+    #[inline]
+    fn format_group_multi_select_quick_key(
+        &self,
+        f: &mut dyn fmt::Write,
+        key: char,
+    ) -> fmt::Result {
+        write!(f, "[{}] ", key)
+    }
+
+    /// Formats the marker briefly appended to an item after it was toggled.
+    #[inline]
+    fn format_group_multi_select_flash_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " •")
+    }
+
+    /// Formats how many items of a group are checked, e.g. " (2/5)".
+    #[inline]
+    fn format_group_multi_select_collapsed_count(
+        &self,
+        f: &mut dyn fmt::Write,
+        checked: usize,
+        total: usize,
+    ) -> fmt::Result {
+        write!(f, " ({}/{})", checked, total)
+    }
+
+    /// Formats the marker appended to the header of a collapsed group.
+    #[inline]
+    fn format_group_multi_select_collapsed_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " …")
+    }
+
+    /// Formats the marker appended to the header of a group violating its constraints.
+    #[inline]
+    fn format_group_multi_select_violation_marker(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " !")
+    }
+
+    /// Formats the tooltip line describing the focused GroupMultiSelect item.
+    #[inline]
+    fn format_group_multi_select_tooltip(&self, f: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+        write!(f, "{}", text)
+    }
+
+    /// Formats the row shown when GroupMultiSelect rows are scrolled out of view above or
+    /// below the page.
+    #[inline]
+    fn format_group_multi_select_scroll_indicator(
+        &self,
+        f: &mut dyn fmt::Write,
+        above: bool,
+    ) -> fmt::Result {
+        write!(f, "  {} more", if above { "↑" } else { "↓" })
+    }
+
+    /// Formats the line describing the key bindings below a GroupMultiSelect list.
+    #[inline]
+    fn format_group_multi_select_help(&self, f: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+        write!(f, "{}", text)
+    }
+
+    /// Formats the error shown below a GroupMultiSelect list when a key press was refused.
+    #[inline]
+    fn format_group_multi_select_error(&self, f: &mut dyn fmt::Write, error: &str) -> fmt::Result {
+        self.format_error(f, error)
+    }
+
+    /// Formats the transient status line shown below a GroupMultiSelect list.
+    #[inline]
+    fn format_group_multi_select_status(
+        &self,
+        f: &mut dyn fmt::Write,
+        status: &str,
+    ) -> fmt::Result {
+        write!(f, "{}", status)
+    }
+
+    /// Formats the search query shown in the prompt line of a GroupMultiSelect.
+    ///
+    /// `bytes_pos` is the position of the text cursor within `query`.
+    #[inline]
+    fn format_group_multi_select_search_query(
+        &self,
+        f: &mut dyn fmt::Write,
+        query: &str,
+        bytes_pos: usize,
+    ) -> fmt::Result {
+        let (head, tail) = query.split_at(bytes_pos);
+        write!(f, " /{head}|{tail}")
+    }
+
+    /// Formats the number of selected items shown after the prompt, along with the
+    /// maximum if there is one.
+    #[inline]
+    fn format_group_multi_select_count(
+        &self,
+        f: &mut dyn fmt::Write,
+        selected: usize,
+        max: Option<usize>,
+    ) -> fmt::Result {
+        match max {
+            Some(max) => write!(f, " ({}/{} selected)", selected, max),
+            None => write!(f, " ({} selected)", selected),
+        }
+    }
+
+    /// Formats the indicator shown in the prompt line while item states are recomputed.
+    #[inline]
+    fn format_group_multi_select_busy_indicator(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, " recomputing…")
+    }
+
+    /// Formats the top border of a boxed GroupMultiSelect list.
+    ///
+    /// `width` is the inner width of the box, excluding the border and its padding.
+    fn format_group_multi_select_box_top(
+        &self,
+        f: &mut dyn fmt::Write,
+        width: usize,
+    ) -> fmt::Result {
+        write!(f, "┌{}┐", "─".repeat(width + 2))
+    }
+
+    /// Formats the bottom border of a boxed GroupMultiSelect list.
+    fn format_group_multi_select_box_bottom(
+        &self,
+        f: &mut dyn fmt::Write,
+        width: usize,
+    ) -> fmt::Result {
+        write!(f, "└{}┘", "─".repeat(width + 2))
+    }
+
+    /// Formats a row of a boxed GroupMultiSelect list.
+    ///
+    /// `row` is already truncated to `width` columns.
+    fn format_group_multi_select_box_row(
+        &self,
+        f: &mut dyn fmt::Write,
+        row: &str,
+        width: usize,
+    ) -> fmt::Result {
+        let padding = width.saturating_sub(measure_text_width(row));
+        write!(f, "│ {}{} │", row, " ".repeat(padding))
+    }
 }
@@ -1,34 +1,66 @@
 use std::{fmt, io};
 
-use console::{measure_text_width, Term};
+use console::{measure_text_width, truncate_str, Term};
 #[cfg(feature = "fuzzy-select")]
 use fuzzy_matcher::skim::SkimMatcherV2;
 
 use crate::{
-    theme::{GroupState, Theme},
+    theme::{RowContext, Theme},
     Result,
 };
 
 /// Helper struct to conveniently render a theme.
 pub(crate) struct TermThemeRenderer<'a> {
-    term: &'a Term,
+    /// Terminal to write to, `None` collects the lines in `lines` instead
+    term: Option<&'a Term>,
+    lines: Vec<String>,
     theme: &'a dyn Theme,
     height: usize,
     prompt_height: usize,
     prompts_reset_height: bool,
+    box_width: Option<usize>,
 }
 
 impl<'a> TermThemeRenderer<'a> {
     pub fn new(term: &'a Term, theme: &'a dyn Theme) -> TermThemeRenderer<'a> {
         TermThemeRenderer {
-            term,
+            term: Some(term),
+            lines: Vec::new(),
             theme,
             height: 0,
             prompt_height: 0,
             prompts_reset_height: true,
+            box_width: None,
         }
     }
 
+    /// Creates a renderer that collects the rendered lines instead of writing them.
+    pub fn buffered(theme: &'a dyn Theme) -> TermThemeRenderer<'a> {
+        TermThemeRenderer {
+            term: None,
+            lines: Vec::new(),
+            theme,
+            height: 0,
+            prompt_height: 0,
+            prompts_reset_height: true,
+            box_width: None,
+        }
+    }
+
+    /// Returns the lines collected by a [`buffered`](Self::buffered) renderer.
+    pub fn into_lines(mut self) -> Vec<String> {
+        // Every written line leaves an empty line open for the next one
+        if self.lines.last().is_some_and(|line| line.is_empty()) {
+            self.lines.pop();
+        }
+        self.lines
+    }
+
+    /// Wraps group multi select rows in a box border of the given inner width.
+    pub fn set_box_width(&mut self, width: Option<usize>) {
+        self.box_width = width;
+    }
+
     #[cfg(feature = "password")]
     pub fn set_prompts_reset_height(&mut self, val: bool) {
         self.prompts_reset_height = val;
@@ -36,13 +68,21 @@ impl<'a> TermThemeRenderer<'a> {
 
     #[cfg(feature = "password")]
     pub fn term(&self) -> &Term {
-        self.term
+        self.term.expect("renderer is not buffered")
     }
 
     pub fn add_line(&mut self) {
         self.height += 1;
     }
 
+    /// Writes lines collected by a [`buffered`](Self::buffered) renderer.
+    pub fn write_lines(&mut self, lines: &[String]) -> Result {
+        for line in lines {
+            self.write_formatted_line(|_, buf| buf.write_str(line))?;
+        }
+        Ok(())
+    }
+
     fn write_formatted_str<
         F: FnOnce(&mut TermThemeRenderer, &mut dyn fmt::Write) -> fmt::Result,
     >(
@@ -52,7 +92,10 @@ impl<'a> TermThemeRenderer<'a> {
         let mut buf = String::new();
         f(self, &mut buf).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
         self.height += buf.chars().filter(|&x| x == '\n').count();
-        self.term.write_str(&buf)?;
+        match self.term {
+            Some(term) => term.write_str(&buf)?,
+            None => self.push_str(&buf),
+        }
         Ok(measure_text_width(&buf))
     }
 
@@ -65,7 +108,42 @@ impl<'a> TermThemeRenderer<'a> {
         let mut buf = String::new();
         f(self, &mut buf).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
         self.height += buf.chars().filter(|&x| x == '\n').count() + 1;
-        Ok(self.term.write_line(&buf)?)
+        match self.term {
+            Some(term) => term.write_line(&buf)?,
+            None => {
+                self.push_str(&buf);
+                self.lines.push(String::new());
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends text to the collected lines, starting a new line after every newline.
+    fn push_str(&mut self, text: &str) {
+        for (idx, part) in text.split('\n').enumerate() {
+            match self.lines.last_mut() {
+                Some(last) if idx == 0 => last.push_str(part),
+                _ => self.lines.push(part.to_string()),
+            }
+        }
+    }
+
+    fn write_formatted_row<
+        F: FnOnce(&mut TermThemeRenderer, &mut dyn fmt::Write) -> fmt::Result,
+    >(
+        &mut self,
+        f: F,
+    ) -> Result {
+        match self.box_width {
+            None => self.write_formatted_line(f),
+            Some(width) => self.write_formatted_line(|this, buf| {
+                let mut row = String::new();
+                f(this, &mut row)?;
+                let row = truncate_str(&row, width, "…");
+                this.theme
+                    .format_group_multi_select_box_row(buf, &row, width)
+            }),
+        }
     }
 
     fn write_formatted_prompt<
@@ -241,13 +319,27 @@ impl<'a> TermThemeRenderer<'a> {
     pub fn group_multi_select_prompt(
         &mut self,
         prompt: &str,
+        count: Option<(usize, Option<usize>)>,
         paging_info: Option<(usize, usize)>,
+        busy: bool,
+        query: Option<&str>,
     ) -> Result {
         self.write_formatted_prompt(|this, buf| {
             this.theme.format_group_multi_select_prompt(buf, prompt)?;
+            if let Some((selected, max)) = count {
+                this.theme
+                    .format_group_multi_select_count(buf, selected, max)?;
+            }
+            if let Some(query) = query {
+                this.theme
+                    .format_group_multi_select_search_query(buf, query, query.len())?;
+            }
             if let Some(paging_info) = paging_info {
                 TermThemeRenderer::write_paging_info(buf, paging_info)?;
             }
+            if busy {
+                this.theme.format_group_multi_select_busy_indicator(buf)?;
+            }
             Ok(())
         })
     }
@@ -259,71 +351,130 @@ impl<'a> TermThemeRenderer<'a> {
         })
     }
 
-    pub fn group_multi_select_header(
+    pub fn group_multi_select_report_counts(
         &mut self,
-        text: &str,
-        state: GroupState,
-        active: bool,
+        selected: usize,
+        total: usize,
+        groups: usize,
     ) -> Result {
         self.write_formatted_line(|this, buf| {
             this.theme
-                .format_group_multi_select_header(buf, text, state, active)
+                .format_group_multi_select_report_counts(buf, selected, total, groups)
         })
     }
 
-    pub fn group_multi_select_item(&mut self, text: &str, checked: bool, active: bool) -> Result {
-        self.write_formatted_line(|this, buf| {
-            this.theme
-                .format_group_multi_select_item(buf, text, checked, active)
-        })
+    pub fn blank_line(&mut self) -> Result {
+        self.write_formatted_line(|_, _| Ok(()))
     }
 
-    pub fn group_multi_select_item_disabled(
+    pub fn group_multi_select_report_continuation(
         &mut self,
-        text: &str,
-        reason: &str,
-        active: bool,
+        indent: usize,
+        selections: &[&str],
     ) -> Result {
         self.write_formatted_line(|this, buf| {
+            write!(buf, "{:indent$}", "", indent = indent)?;
             this.theme
-                .format_group_multi_select_item_disabled(buf, text, reason, active)
+                .format_group_multi_select_report_continuation(buf, selections)
         })
     }
 
-    pub fn group_multi_select_item_warning(
-        &mut self,
-        text: &str,
-        message: &str,
-        checked: bool,
-        active: bool,
-    ) -> Result {
+    pub fn group_multi_select_quit_report(&mut self, prompt: &str, message: &str) -> Result {
+        self.write_formatted_prompt(|this, buf| {
+            this.theme
+                .format_group_multi_select_quit_report(buf, prompt, message)
+        })
+    }
+
+    pub fn group_multi_select_row(&mut self, row: &RowContext<'_>) -> Result {
+        self.write_formatted_row(|this, buf| this.theme.format_group_multi_select_row(buf, row))
+    }
+
+    pub fn group_multi_select_subtitle(&mut self, text: &str) -> Result {
+        self.write_formatted_row(|this, buf| {
+            this.theme.format_group_multi_select_subtitle(buf, text)
+        })
+    }
+
+    pub fn group_multi_select_prologue(&mut self, text: &str) -> Result {
+        self.write_formatted_row(|this, buf| {
+            this.theme.format_group_multi_select_prologue(buf, text)
+        })
+    }
+
+    pub fn group_multi_select_tooltip(&mut self, text: &str) -> Result {
+        self.write_formatted_line(|this, buf| {
+            this.theme.format_group_multi_select_tooltip(buf, text)
+        })
+    }
+
+    pub fn group_multi_select_scroll_indicator(&mut self, above: bool) -> Result {
         self.write_formatted_line(|this, buf| {
             this.theme
-                .format_group_multi_select_item_warning(buf, text, message, checked, active)
+                .format_group_multi_select_scroll_indicator(buf, above)
         })
     }
 
+    pub fn group_multi_select_help(&mut self, text: &str) -> Result {
+        self.write_formatted_line(|this, buf| this.theme.format_group_multi_select_help(buf, text))
+    }
+
+    pub fn group_multi_select_status(&mut self, status: &str) -> Result {
+        self.write_formatted_line(|this, buf| {
+            this.theme.format_group_multi_select_status(buf, status)
+        })
+    }
+
+    pub fn group_multi_select_error(&mut self, error: &str) -> Result {
+        self.write_formatted_line(|this, buf| {
+            this.theme.format_group_multi_select_error(buf, error)
+        })
+    }
+
+    pub fn group_multi_select_box_top(&mut self, width: usize) -> Result {
+        self.write_formatted_line(|this, buf| {
+            this.theme.format_group_multi_select_box_top(buf, width)
+        })
+    }
+
+    pub fn group_multi_select_box_bottom(&mut self, width: usize) -> Result {
+        self.write_formatted_line(|this, buf| {
+            this.theme.format_group_multi_select_box_bottom(buf, width)
+        })
+    }
+
+    pub fn group_multi_select_box_padding(&mut self) -> Result {
+        self.write_formatted_row(|_, _| Ok(()))
+    }
+
     pub fn clear(&mut self) -> Result {
-        self.term
-            .clear_last_lines(self.height + self.prompt_height)?;
+        match self.term {
+            Some(term) => term.clear_last_lines(self.height + self.prompt_height)?,
+            None => self.lines.clear(),
+        }
         self.height = 0;
         self.prompt_height = 0;
         Ok(())
     }
 
     pub fn clear_preserve_prompt(&mut self, size_vec: &[usize]) -> Result {
+        let Some(term) = self.term else {
+            self.lines.clear();
+            self.height = 0;
+            return Ok(());
+        };
         let mut new_height = self.height;
         let prefix_width = 2;
         //Check each item size, increment on finding an overflow
         for size in size_vec {
-            if *size > self.term.size().1 as usize {
-                new_height += (((*size as f64 + prefix_width as f64) / self.term.size().1 as f64)
-                    .ceil()) as usize
+            if *size > term.size().1 as usize {
+                new_height += (((*size as f64 + prefix_width as f64) / term.size().1 as f64).ceil())
+                    as usize
                     - 1;
             }
         }
 
-        self.term.clear_last_lines(new_height)?;
+        term.clear_last_lines(new_height)?;
         self.height = 0;
         Ok(())
     }